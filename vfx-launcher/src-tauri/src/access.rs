@@ -0,0 +1,261 @@
+use serde::{Serialize, Deserialize};
+use rusqlite::{params, Connection, OptionalExtension};
+use chrono::Utc;
+use crate::activity::{self, ActivityType};
+use crate::db;
+use crate::logger;
+
+// SQL condition limiting `p` (projects) to the ones visible to the user bound at `?`.
+// Projects without any members are unrestricted and stay visible to everyone.
+pub const VISIBLE_PROJECT_FILTER: &str =
+    "(NOT EXISTS(SELECT 1 FROM project_members m WHERE m.project_id = p.id)
+      OR EXISTS(SELECT 1 FROM project_members m WHERE m.project_id = p.id AND m.user_id = ?))";
+
+// Role a user holds on a single project
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProjectRole {
+    Owner,
+    Member,
+    ReadOnly,
+}
+
+impl ProjectRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProjectRole::Owner => "owner",
+            ProjectRole::Member => "member",
+            ProjectRole::ReadOnly => "read_only",
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<ProjectRole, String> {
+        match value {
+            "owner" => Ok(ProjectRole::Owner),
+            "member" => Ok(ProjectRole::Member),
+            "read_only" => Ok(ProjectRole::ReadOnly),
+            other => Err(format!("Unknown project role: {}", other)),
+        }
+    }
+
+    // Whether this role may open (and therefore modify) project files
+    pub fn can_open_files(&self) -> bool {
+        !matches!(self, ProjectRole::ReadOnly)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ProjectMember {
+    pub project_id: i64,
+    pub user_id: i64,
+    pub username: String,
+    pub role: ProjectRole,
+    pub created_at: String,
+}
+
+// What a user is allowed to do on a project after membership and admin checks
+#[derive(Debug, PartialEq)]
+pub enum ProjectAccess {
+    // The project has no members, so everyone may use it
+    Unrestricted,
+    Member(ProjectRole),
+    AdminOverride,
+    Denied,
+}
+
+pub fn is_admin(conn: &Connection, user_id: i64) -> bool {
+    conn.query_row(
+        "SELECT role = 'admin' FROM users WHERE id = ?",
+        params![user_id],
+        |row| row.get(0)
+    ).unwrap_or(false)
+}
+
+fn project_is_restricted(conn: &Connection, project_id: i64) -> Result<bool, String> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM project_members WHERE project_id = ?)",
+        params![project_id],
+        |row| row.get(0)
+    ).map_err(|e| e.to_string())
+}
+
+pub fn get_member_role(conn: &Connection, project_id: i64, user_id: i64) -> Result<Option<ProjectRole>, String> {
+    let role: Option<String> = conn.query_row(
+        "SELECT role FROM project_members WHERE project_id = ? AND user_id = ?",
+        params![project_id, user_id],
+        |row| row.get(0)
+    ).optional().map_err(|e| e.to_string())?;
+
+    role.map(|r| ProjectRole::parse(&r)).transpose()
+}
+
+// Resolve how `user_id` may access `project_id`
+pub fn resolve_access(conn: &Connection, project_id: i64, user_id: Option<i64>) -> Result<ProjectAccess, String> {
+    if !project_is_restricted(conn, project_id)? {
+        return Ok(ProjectAccess::Unrestricted);
+    }
+
+    let uid = match user_id {
+        Some(uid) => uid,
+        None => return Ok(ProjectAccess::Denied),
+    };
+
+    if let Some(role) = get_member_role(conn, project_id, uid)? {
+        return Ok(ProjectAccess::Member(role));
+    }
+
+    if is_admin(conn, uid) {
+        return Ok(ProjectAccess::AdminOverride);
+    }
+
+    Ok(ProjectAccess::Denied)
+}
+
+// Fail unless the user may view the project. Admin overrides are written to the activity log.
+pub fn require_project_access(conn: &Connection, project_id: i64, user_id: Option<i64>) -> Result<ProjectAccess, String> {
+    let access = resolve_access(conn, project_id, user_id)?;
+    match access {
+        ProjectAccess::Denied => {
+            logger::warn(&format!("Access to project {} denied for user {:?}", project_id, user_id));
+            Err("You are not a member of this project".to_string())
+        },
+        ProjectAccess::AdminOverride => {
+            if let Some(uid) = user_id {
+                activity::record_activity(conn, uid, ActivityType::AdminOverride, Some(project_id), None, "Admin accessed restricted project without membership");
+            }
+            Ok(access)
+        },
+        _ => Ok(access),
+    }
+}
+
+// Fail unless the user may open files in the project (read-only members may not)
+pub fn require_open_access(conn: &Connection, project_id: i64, user_id: Option<i64>) -> Result<(), String> {
+    match require_project_access(conn, project_id, user_id)? {
+        ProjectAccess::Member(role) if !role.can_open_files() => {
+            logger::warn(&format!("User {:?} has read-only access to project {}", user_id, project_id));
+            Err("You have read-only access to this project".to_string())
+        },
+        _ => Ok(()),
+    }
+}
+
+// Only admins and project owners may manage a project's membership and settings
+pub fn require_project_owner(conn: &Connection, project_id: i64, acting_user_id: i64) -> Result<(), String> {
+    if is_admin(conn, acting_user_id) {
+        return Ok(());
+    }
+    match get_member_role(conn, project_id, acting_user_id)? {
+        Some(ProjectRole::Owner) => Ok(()),
+        _ => Err("Only project owners and admins can change this project".to_string()),
+    }
+}
+
+#[tauri::command]
+pub fn get_project_members(project_id: i64, user_id: Option<i64>) -> Result<Vec<ProjectMember>, String> {
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    require_project_access(&conn, project_id, user_id)?;
+
+    let mut stmt = conn.prepare(
+        "SELECT m.project_id, m.user_id, u.username, m.role, m.created_at
+         FROM project_members m
+         JOIN users u ON m.user_id = u.id
+         WHERE m.project_id = ?
+         ORDER BY u.username ASC"
+    ).map_err(|e| e.to_string())?;
+
+    let rows = stmt.query_map(params![project_id], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            row.get::<_, i64>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, String>(3)?,
+            row.get::<_, String>(4)?,
+        ))
+    }).map_err(|e| e.to_string())?;
+
+    let mut members = Vec::new();
+    for row in rows {
+        let (project_id, user_id, username, role, created_at) = row.map_err(|e| e.to_string())?;
+        members.push(ProjectMember {
+            project_id,
+            user_id,
+            username,
+            role: ProjectRole::parse(&role)?,
+            created_at,
+        });
+    }
+
+    Ok(members)
+}
+
+// Add a member to a project or change the role of an existing member
+#[tauri::command]
+pub fn set_project_member(
+    project_id: i64,
+    member_user_id: i64,
+    role: ProjectRole,
+    acting_user_id: i64
+) -> Result<bool, String> {
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+
+    // Restricting an open project is an admin decision; afterwards owners manage membership too
+    if project_is_restricted(&conn, project_id)? {
+        require_project_owner(&conn, project_id, acting_user_id)?;
+    } else if !is_admin(&conn, acting_user_id) {
+        return Err("Only admins can restrict a project to its members".to_string());
+    }
+
+    let previous = get_member_role(&conn, project_id, member_user_id)?;
+    let now = Utc::now().to_rfc3339();
+    conn.execute(
+        "INSERT INTO project_members (project_id, user_id, role, created_at) VALUES (?, ?, ?, ?)
+         ON CONFLICT(project_id, user_id) DO UPDATE SET role = excluded.role",
+        params![project_id, member_user_id, role.as_str(), now],
+    ).map_err(|e| e.to_string())?;
+
+    let (activity_type, details) = match previous {
+        Some(old) => (ActivityType::UpdateProjectMember, format!("Changed user {} role from {} to {}", member_user_id, old.as_str(), role.as_str())),
+        None => (ActivityType::AddProjectMember, format!("Added user {} as {}", member_user_id, role.as_str())),
+    };
+    activity::record_activity(&conn, acting_user_id, activity_type, Some(project_id), None, &details);
+    logger::info(&format!("Project {}: {}", project_id, details));
+
+    Ok(true)
+}
+
+#[tauri::command]
+pub fn remove_project_member(project_id: i64, member_user_id: i64, acting_user_id: i64) -> Result<bool, String> {
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    require_project_owner(&conn, project_id, acting_user_id)?;
+
+    // Keep at least one owner so the project does not become unmanageable for non-admins
+    if get_member_role(&conn, project_id, member_user_id)? == Some(ProjectRole::Owner) {
+        let owner_count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM project_members WHERE project_id = ? AND role = 'owner'",
+            params![project_id],
+            |row| row.get(0)
+        ).map_err(|e| e.to_string())?;
+        let member_count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM project_members WHERE project_id = ?",
+            params![project_id],
+            |row| row.get(0)
+        ).map_err(|e| e.to_string())?;
+        if owner_count <= 1 && member_count > 1 {
+            return Err("Cannot remove the last owner of a project".to_string());
+        }
+    }
+
+    let removed = conn.execute(
+        "DELETE FROM project_members WHERE project_id = ? AND user_id = ?",
+        params![project_id, member_user_id],
+    ).map_err(|e| e.to_string())?;
+
+    if removed > 0 {
+        let details = format!("Removed user {} from project", member_user_id);
+        activity::record_activity(&conn, acting_user_id, ActivityType::RemoveProjectMember, Some(project_id), None, &details);
+        logger::info(&format!("Project {}: {}", project_id, details));
+    }
+
+    Ok(removed > 0)
+}
@@ -0,0 +1,477 @@
+use std::collections::BTreeMap;
+use chrono::{DateTime, Local, NaiveDate, TimeZone, Utc};
+use rusqlite::{params, params_from_iter, Connection, ToSql};
+use serde::{Serialize, Deserialize};
+use crate::access;
+use crate::db;
+use crate::logger;
+
+// Rows returned per page when no limit is given, and the most a page may hold
+const DEFAULT_PAGE: i64 = 100;
+pub const MAX_PAGE: i64 = 1000;
+
+// What a user_activity row records. Stored as its snake_case name; names written by
+// older versions that are not listed here read back as Other.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(from = "String", into = "String")]
+pub enum ActivityType {
+    Login,
+    OpenFile,
+    AddProject,
+    DeleteProject,
+    AddFavorite,
+    RemoveFavorite,
+    AddUser,
+    UpdateUser,
+    DeleteUser,
+    CheckoutFile,
+    CheckinFile,
+    ForceReleaseLock,
+    AdminOverride,
+    AddProjectMember,
+    UpdateProjectMember,
+    RemoveProjectMember,
+    PinApplication,
+    UnpinApplication,
+    SaveEnvironmentVariable,
+    DeleteEnvironmentVariable,
+    SessionEnd,
+    VersionUp,
+    NewScript,
+    LocalizeScript,
+    LocalizeScripts,
+    ArchiveActivity,
+    Other(String),
+}
+
+const KNOWN_TYPES: &[ActivityType] = &[
+    ActivityType::Login,
+    ActivityType::OpenFile,
+    ActivityType::AddProject,
+    ActivityType::DeleteProject,
+    ActivityType::AddFavorite,
+    ActivityType::RemoveFavorite,
+    ActivityType::AddUser,
+    ActivityType::UpdateUser,
+    ActivityType::DeleteUser,
+    ActivityType::CheckoutFile,
+    ActivityType::CheckinFile,
+    ActivityType::ForceReleaseLock,
+    ActivityType::AdminOverride,
+    ActivityType::AddProjectMember,
+    ActivityType::UpdateProjectMember,
+    ActivityType::RemoveProjectMember,
+    ActivityType::PinApplication,
+    ActivityType::UnpinApplication,
+    ActivityType::SaveEnvironmentVariable,
+    ActivityType::DeleteEnvironmentVariable,
+    ActivityType::SessionEnd,
+    ActivityType::VersionUp,
+    ActivityType::NewScript,
+    ActivityType::LocalizeScript,
+    ActivityType::LocalizeScripts,
+    ActivityType::ArchiveActivity,
+];
+
+impl ActivityType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ActivityType::Login => "login",
+            ActivityType::OpenFile => "open_file",
+            ActivityType::AddProject => "add_project",
+            ActivityType::DeleteProject => "delete_project",
+            ActivityType::AddFavorite => "add_favorite",
+            ActivityType::RemoveFavorite => "remove_favorite",
+            ActivityType::AddUser => "add_user",
+            ActivityType::UpdateUser => "update_user",
+            ActivityType::DeleteUser => "delete_user",
+            ActivityType::CheckoutFile => "checkout_file",
+            ActivityType::CheckinFile => "checkin_file",
+            ActivityType::ForceReleaseLock => "force_release_lock",
+            ActivityType::AdminOverride => "admin_override",
+            ActivityType::AddProjectMember => "add_project_member",
+            ActivityType::UpdateProjectMember => "update_project_member",
+            ActivityType::RemoveProjectMember => "remove_project_member",
+            ActivityType::PinApplication => "pin_application",
+            ActivityType::UnpinApplication => "unpin_application",
+            ActivityType::SaveEnvironmentVariable => "save_environment_variable",
+            ActivityType::DeleteEnvironmentVariable => "delete_environment_variable",
+            ActivityType::SessionEnd => "session_end",
+            ActivityType::VersionUp => "version_up",
+            ActivityType::NewScript => "new_script",
+            ActivityType::LocalizeScript => "localize_script",
+            ActivityType::LocalizeScripts => "localize_scripts",
+            ActivityType::ArchiveActivity => "archive_activity",
+            ActivityType::Other(name) => name,
+        }
+    }
+}
+
+impl From<String> for ActivityType {
+    fn from(value: String) -> Self {
+        KNOWN_TYPES.iter()
+            .find(|known| known.as_str() == value)
+            .cloned()
+            .unwrap_or(ActivityType::Other(value))
+    }
+}
+
+impl From<ActivityType> for String {
+    fn from(value: ActivityType) -> Self {
+        value.as_str().to_string()
+    }
+}
+
+impl ToSql for ActivityType {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        Ok(self.as_str().into())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ActivityRecord {
+    pub id: i64,
+    pub user_id: i64,
+    pub username: Option<String>,
+    pub activity_type: ActivityType,
+    pub project_id: Option<i64>,
+    pub project_name: Option<String>,
+    pub file_id: Option<i64>,
+    pub file_name: Option<String>,
+    pub details: Option<String>,
+    pub timestamp: String,
+}
+
+// Filters shared by the listing and the aggregates. Times are RFC 3339 or a local
+// "YYYY-MM-DD"; `until` is exclusive.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ActivityQuery {
+    pub user_id: Option<i64>,
+    #[serde(default)]
+    pub activity_types: Vec<ActivityType>,
+    pub project_id: Option<i64>,
+    pub file_id: Option<i64>,
+    pub since: Option<String>,
+    pub until: Option<String>,
+    // next_cursor of the previous page
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+    // Rows up to this id only, so an archive holds exactly the rows it later deletes
+    #[serde(skip)]
+    pub max_id: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ActivityPage {
+    pub records: Vec<ActivityRecord>,
+    // Pass back as `cursor` for the next (older) page; None on the last page
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DailyCount {
+    pub project_id: i64,
+    pub project_name: Option<String>,
+    // UTC day, "YYYY-MM-DD"
+    pub day: String,
+    pub count: i64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FileActivity {
+    pub file_id: i64,
+    pub file_name: Option<String>,
+    pub project_id: Option<i64>,
+    pub count: i64,
+    pub last_activity: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct UserSummary {
+    pub user_id: i64,
+    pub username: Option<String>,
+    pub total: i64,
+    pub by_type: BTreeMap<String, i64>,
+    pub first_activity: String,
+    pub last_activity: String,
+}
+
+// Insert an activity row on an existing connection
+pub fn insert_activity(
+    conn: &Connection,
+    user_id: i64,
+    activity_type: ActivityType,
+    project_id: Option<i64>,
+    file_id: Option<i64>,
+    details: Option<&str>
+) -> Result<i64, String> {
+    let now = Utc::now().to_rfc3339();
+    conn.execute(
+        "INSERT INTO user_activity (user_id, activity_type, project_id, file_id, details, timestamp)
+         VALUES (?, ?, ?, ?, ?, ?)",
+        params![user_id, activity_type, project_id, file_id, details, now]
+    ).map_err(|e| e.to_string())?;
+
+    Ok(conn.last_insert_rowid())
+}
+
+// Record activity from backend code paths where a logging failure must not fail the operation
+pub fn record_activity(
+    conn: &Connection,
+    user_id: i64,
+    activity_type: ActivityType,
+    project_id: Option<i64>,
+    file_id: Option<i64>,
+    details: &str
+) {
+    let name = activity_type.as_str().to_string();
+    if let Err(e) = insert_activity(conn, user_id, activity_type, project_id, file_id, Some(details)) {
+        logger::warn(&format!("Failed to log {} activity: {}", name, e));
+    }
+}
+
+// Stored timestamps are UTC RFC 3339, so bounds are converted to the same form and compared as text
+fn parse_bound(value: &str) -> Result<String, String> {
+    let value = value.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc).to_rfc3339());
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .and_then(|midnight| Local.from_local_datetime(&midnight).earliest())
+        .map(|time| time.with_timezone(&Utc).to_rfc3339())
+        .ok_or_else(|| format!("Unrecognised date \"{}\", expected RFC 3339 or YYYY-MM-DD", value))
+}
+
+// Cursors are "<timestamp>|<id>" of the last row on a page
+fn parse_cursor(cursor: &str) -> Result<(String, i64), String> {
+    cursor.rsplit_once('|')
+        .and_then(|(timestamp, id)| Some((timestamp.to_string(), id.parse().ok()?)))
+        .ok_or_else(|| format!("Invalid activity cursor \"{}\"", cursor))
+}
+
+// Users other than admins only ever see their own activity
+pub fn scope_to_user(conn: &Connection, mut query: ActivityQuery, acting_user_id: i64) -> ActivityQuery {
+    if !access::is_admin(conn, acting_user_id) {
+        query.user_id = Some(acting_user_id);
+    }
+    query
+}
+
+// WHERE clause over user_activity aliased "a", and its parameters
+fn build_filter(query: &ActivityQuery) -> Result<(String, Vec<Box<dyn ToSql>>), String> {
+    let mut clauses: Vec<String> = Vec::new();
+    let mut values: Vec<Box<dyn ToSql>> = Vec::new();
+
+    if let Some(uid) = query.user_id {
+        clauses.push("a.user_id = ?".to_string());
+        values.push(Box::new(uid));
+    }
+    if !query.activity_types.is_empty() {
+        clauses.push(format!("a.activity_type IN ({})", vec!["?"; query.activity_types.len()].join(", ")));
+        for activity_type in &query.activity_types {
+            values.push(Box::new(activity_type.clone()));
+        }
+    }
+    if let Some(project_id) = query.project_id {
+        clauses.push("a.project_id = ?".to_string());
+        values.push(Box::new(project_id));
+    }
+    if let Some(file_id) = query.file_id {
+        clauses.push("a.file_id = ?".to_string());
+        values.push(Box::new(file_id));
+    }
+    if let Some(since) = query.since.as_deref().filter(|s| !s.trim().is_empty()) {
+        clauses.push("a.timestamp >= ?".to_string());
+        values.push(Box::new(parse_bound(since)?));
+    }
+    if let Some(until) = query.until.as_deref().filter(|s| !s.trim().is_empty()) {
+        clauses.push("a.timestamp < ?".to_string());
+        values.push(Box::new(parse_bound(until)?));
+    }
+    if let Some(max_id) = query.max_id {
+        clauses.push("a.id <= ?".to_string());
+        values.push(Box::new(max_id));
+    }
+
+    let sql = if clauses.is_empty() { String::from("1=1") } else { clauses.join(" AND ") };
+    Ok((sql, values))
+}
+
+pub fn query_activity(conn: &Connection, query: &ActivityQuery) -> Result<ActivityPage, String> {
+    let (mut filter, mut values) = build_filter(query)?;
+    if let Some(cursor) = query.cursor.as_deref().filter(|c| !c.is_empty()) {
+        let (timestamp, id) = parse_cursor(cursor)?;
+        filter.push_str(" AND (a.timestamp < ? OR (a.timestamp = ? AND a.id < ?))");
+        values.push(Box::new(timestamp.clone()));
+        values.push(Box::new(timestamp));
+        values.push(Box::new(id));
+    }
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE).clamp(1, MAX_PAGE);
+    // One extra row tells whether there is another page
+    values.push(Box::new(limit + 1));
+
+    let sql = format!(
+        "SELECT a.id, a.user_id, u.username, a.activity_type, a.project_id, p.name, a.file_id, f.filename, a.details, a.timestamp
+         FROM user_activity a
+         LEFT JOIN users u ON a.user_id = u.id
+         LEFT JOIN projects p ON a.project_id = p.id
+         LEFT JOIN project_files f ON a.file_id = f.id
+         WHERE {}
+         ORDER BY a.timestamp DESC, a.id DESC
+         LIMIT ?",
+        filter
+    );
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let mut records = stmt.query_map(params_from_iter(values.iter()), |row| {
+        Ok(ActivityRecord {
+            id: row.get(0)?,
+            user_id: row.get(1)?,
+            username: row.get(2)?,
+            activity_type: ActivityType::from(row.get::<_, String>(3)?),
+            project_id: row.get(4)?,
+            project_name: row.get(5)?,
+            file_id: row.get(6)?,
+            file_name: row.get(7)?,
+            details: row.get(8)?,
+            timestamp: row.get(9)?,
+        })
+    }).map_err(|e| e.to_string())?
+        .collect::<rusqlite::Result<Vec<ActivityRecord>>>()
+        .map_err(|e| e.to_string())?;
+
+    let next_cursor = if records.len() as i64 > limit {
+        records.truncate(limit as usize);
+        records.last().map(|last| format!("{}|{}", last.timestamp, last.id))
+    } else {
+        None
+    };
+    Ok(ActivityPage { records, next_cursor })
+}
+
+// Log user activity
+#[tauri::command]
+pub fn log_activity(
+    user_id: i64,
+    activity_type: ActivityType,
+    project_id: Option<i64>,
+    file_id: Option<i64>,
+    details: Option<String>
+) -> Result<i64, String> {
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    insert_activity(&conn, user_id, activity_type, project_id, file_id, details.as_deref())
+}
+
+// One page of activity matching the query, newest first
+#[tauri::command]
+pub fn get_activity(query: ActivityQuery, acting_user_id: i64) -> Result<ActivityPage, String> {
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    query_activity(&conn, &scope_to_user(&conn, query, acting_user_id))
+}
+
+// File opens per project per UTC day
+#[tauri::command]
+pub fn get_opens_per_project_per_day(query: ActivityQuery, acting_user_id: i64) -> Result<Vec<DailyCount>, String> {
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    let query = scope_to_user(&conn, query, acting_user_id);
+    let (filter, mut values) = build_filter(&query)?;
+    values.push(Box::new(ActivityType::OpenFile));
+    let sql = format!(
+        "SELECT a.project_id, p.name, substr(a.timestamp, 1, 10) AS day, COUNT(*)
+         FROM user_activity a
+         LEFT JOIN projects p ON a.project_id = p.id
+         WHERE {} AND a.activity_type = ? AND a.project_id IS NOT NULL
+         GROUP BY a.project_id, day
+         ORDER BY day DESC, COUNT(*) DESC",
+        filter
+    );
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt.query_map(params_from_iter(values.iter()), |row| {
+        Ok(DailyCount { project_id: row.get(0)?, project_name: row.get(1)?, day: row.get(2)?, count: row.get(3)? })
+    }).map_err(|e| e.to_string())?;
+    rows.collect::<rusqlite::Result<Vec<DailyCount>>>().map_err(|e| e.to_string())
+}
+
+// Files with the most activity of any kind matching the query
+#[tauri::command]
+pub fn get_most_active_files(query: ActivityQuery, acting_user_id: i64) -> Result<Vec<FileActivity>, String> {
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    let query = scope_to_user(&conn, query, acting_user_id);
+    let (filter, mut values) = build_filter(&query)?;
+    values.push(Box::new(query.limit.unwrap_or(20).clamp(1, MAX_PAGE)));
+    let sql = format!(
+        "SELECT a.file_id, f.filename, COALESCE(f.project_id, MAX(a.project_id)), COUNT(*), MAX(a.timestamp)
+         FROM user_activity a
+         LEFT JOIN project_files f ON a.file_id = f.id
+         WHERE {} AND a.file_id IS NOT NULL
+         GROUP BY a.file_id
+         ORDER BY COUNT(*) DESC, MAX(a.timestamp) DESC
+         LIMIT ?",
+        filter
+    );
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt.query_map(params_from_iter(values.iter()), |row| {
+        Ok(FileActivity {
+            file_id: row.get(0)?,
+            file_name: row.get(1)?,
+            project_id: row.get(2)?,
+            count: row.get(3)?,
+            last_activity: row.get(4)?,
+        })
+    }).map_err(|e| e.to_string())?;
+    rows.collect::<rusqlite::Result<Vec<FileActivity>>>().map_err(|e| e.to_string())
+}
+
+// Totals per user, broken down by activity type
+#[tauri::command]
+pub fn get_user_activity_summary(query: ActivityQuery, acting_user_id: i64) -> Result<Vec<UserSummary>, String> {
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    let (filter, values) = build_filter(&scope_to_user(&conn, query, acting_user_id))?;
+    let sql = format!(
+        "SELECT a.user_id, u.username, a.activity_type, COUNT(*), MIN(a.timestamp), MAX(a.timestamp)
+         FROM user_activity a
+         LEFT JOIN users u ON a.user_id = u.id
+         WHERE {}
+         GROUP BY a.user_id, a.activity_type
+         ORDER BY a.user_id",
+        filter
+    );
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt.query_map(params_from_iter(values.iter()), |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            row.get::<_, Option<String>>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, i64>(3)?,
+            row.get::<_, String>(4)?,
+            row.get::<_, String>(5)?,
+        ))
+    }).map_err(|e| e.to_string())?;
+
+    let mut summaries: Vec<UserSummary> = Vec::new();
+    for row in rows {
+        let (user_id, username, activity_type, count, first, last) = row.map_err(|e| e.to_string())?;
+        if summaries.last().map(|s| s.user_id) != Some(user_id) {
+            summaries.push(UserSummary {
+                user_id,
+                username,
+                total: 0,
+                by_type: BTreeMap::new(),
+                first_activity: first.clone(),
+                last_activity: last.clone(),
+            });
+        }
+        let summary = summaries.last_mut().unwrap();
+        summary.total += count;
+        summary.by_type.insert(activity_type, count);
+        if first < summary.first_activity {
+            summary.first_activity = first;
+        }
+        if last > summary.last_activity {
+            summary.last_activity = last;
+        }
+    }
+    summaries.sort_by_key(|s| std::cmp::Reverse(s.total));
+    Ok(summaries)
+}
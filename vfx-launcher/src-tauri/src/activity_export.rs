@@ -0,0 +1,327 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Duration, Local, Utc};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use rusqlite::{params, Connection, TransactionBehavior};
+use serde::{Serialize, Deserialize};
+use crate::access;
+use crate::activity::{self, ActivityQuery, ActivityRecord, ActivityType};
+use crate::config::{self, ActivityConfig, ExportFormat};
+use crate::db;
+use crate::logger;
+use crate::paths;
+
+const CSV_COLUMNS: &[&str] = &[
+    "id", "timestamp", "user_id", "username", "activity_type",
+    "project_id", "project_name", "file_id", "file_name", "details",
+];
+
+// How often the retention thread wakes to see whether an archive run is due
+const RETENTION_CHECK: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ActivityExport {
+    pub path: String,
+    pub rows: usize,
+}
+
+// One retention run that archived and removed rows
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ActivityArchive {
+    pub id: i64,
+    pub machine: String,
+    // Admin who ran it; None for scheduled runs
+    pub user_id: Option<i64>,
+    // Rows older than this were archived
+    pub cutoff: String,
+    pub rows: i64,
+    pub path: String,
+    pub created_at: String,
+}
+
+// Writes records as they are read so exporting the whole table never holds it in memory
+struct RecordWriter<W: Write> {
+    out: W,
+    format: ExportFormat,
+    rows: usize,
+}
+
+impl<W: Write> RecordWriter<W> {
+    fn new(mut out: W, format: ExportFormat) -> io::Result<Self> {
+        match format {
+            ExportFormat::Csv => writeln!(out, "{}", CSV_COLUMNS.join(","))?,
+            ExportFormat::Json => out.write_all(b"[")?,
+        }
+        Ok(RecordWriter { out, format, rows: 0 })
+    }
+
+    fn write(&mut self, record: &ActivityRecord) -> io::Result<()> {
+        match self.format {
+            ExportFormat::Csv => {
+                let optional = |value: Option<i64>| value.map(|v| v.to_string()).unwrap_or_default();
+                let fields = [
+                    record.id.to_string(),
+                    record.timestamp.clone(),
+                    record.user_id.to_string(),
+                    record.username.clone().unwrap_or_default(),
+                    record.activity_type.as_str().to_string(),
+                    optional(record.project_id),
+                    record.project_name.clone().unwrap_or_default(),
+                    optional(record.file_id),
+                    record.file_name.clone().unwrap_or_default(),
+                    record.details.clone().unwrap_or_default(),
+                ];
+                let line: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+                writeln!(self.out, "{}", line.join(","))?;
+            },
+            ExportFormat::Json => {
+                if self.rows > 0 {
+                    self.out.write_all(b",")?;
+                }
+                self.out.write_all(b"\n  ")?;
+                serde_json::to_writer(&mut self.out, record)?;
+            },
+        }
+        self.rows += 1;
+        Ok(())
+    }
+
+    fn finish(mut self) -> io::Result<(W, usize)> {
+        if self.format == ExportFormat::Json {
+            self.out.write_all(b"\n]\n")?;
+        }
+        self.out.flush()?;
+        Ok((self.out, self.rows))
+    }
+}
+
+// Quote a CSV field when it holds a separator, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn extension(format: ExportFormat) -> &'static str {
+    match format {
+        ExportFormat::Csv => "csv",
+        ExportFormat::Json => "json",
+    }
+}
+
+// Feed every record matching the query to the writer, a page at a time. The query's own
+// cursor and limit are ignored.
+fn write_matching<W: Write>(conn: &Connection, query: &ActivityQuery, writer: &mut RecordWriter<W>) -> Result<(), String> {
+    let mut page_query = ActivityQuery { cursor: None, limit: Some(activity::MAX_PAGE), ..query.clone() };
+    loop {
+        let page = activity::query_activity(conn, &page_query)?;
+        for record in &page.records {
+            writer.write(record).map_err(|e| e.to_string())?;
+        }
+        match page.next_cursor {
+            Some(cursor) => page_query.cursor = Some(cursor),
+            None => return Ok(()),
+        }
+    }
+}
+
+fn default_export_path(format: ExportFormat) -> PathBuf {
+    let dir = dirs::download_dir().or_else(dirs::home_dir).unwrap_or_else(|| PathBuf::from("."));
+    dir.join(format!("vfx-activity-{}.{}", Local::now().format("%Y%m%d-%H%M%S"), extension(format)))
+}
+
+// Write all activity matching the query to a CSV or JSON file, newest first. Users other
+// than admins export only their own activity.
+#[tauri::command]
+pub fn export_activity(
+    query: ActivityQuery,
+    format: ExportFormat,
+    output_path: Option<String>,
+    acting_user_id: i64
+) -> Result<ActivityExport, String> {
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    let query = activity::scope_to_user(&conn, query, acting_user_id);
+    let path = output_path.filter(|p| !p.trim().is_empty()).map(PathBuf::from).unwrap_or_else(|| default_export_path(format));
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+
+    let file = File::create(&path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let mut writer = RecordWriter::new(BufWriter::new(file), format).map_err(|e| e.to_string())?;
+    write_matching(&conn, &query, &mut writer)?;
+    let (_, rows) = writer.finish().map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+    logger::info(&format!("Exported {} activity rows to {}", rows, path.display()));
+    Ok(ActivityExport { path: path.to_string_lossy().to_string(), rows })
+}
+
+// Write the archive under a temporary name and rename it once complete
+fn write_archive(conn: &Connection, cutoff: &str, max_id: i64, format: ExportFormat, path: &Path) -> Result<usize, String> {
+    let partial = path.with_extension("gz.part");
+    let result = (|| -> Result<usize, String> {
+        let file = File::create(&partial).map_err(|e| format!("Failed to create {}: {}", partial.display(), e))?;
+        let encoder = GzEncoder::new(BufWriter::new(file), Compression::default());
+        let mut writer = RecordWriter::new(encoder, format).map_err(|e| e.to_string())?;
+        let query = ActivityQuery { until: Some(cutoff.to_string()), max_id: Some(max_id), ..Default::default() };
+        write_matching(conn, &query, &mut writer)?;
+        let (encoder, rows) = writer.finish().map_err(|e| e.to_string())?;
+        encoder.finish().and_then(|mut out| out.flush()).map_err(|e| format!("Failed to write {}: {}", partial.display(), e))?;
+        fs::rename(&partial, path).map_err(|e| format!("Failed to rename {}: {}", partial.display(), e))?;
+        Ok(rows)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&partial);
+    }
+    result
+}
+
+// Archive activity older than `days` into a compressed export and delete it. The export runs
+// without a write lock, up to the newest row due when it started; the delete and the
+// activity_archives row then go in one short transaction. Returns None when there was nothing
+// to archive, or when another workstation archived the same rows first.
+fn archive_older_than(conn: &mut Connection, days: u64, user_id: Option<i64>) -> Result<Option<ActivityArchive>, String> {
+    let cfg = config::get_config().activity.clone();
+    let cutoff = (Utc::now() - Duration::days(days as i64)).to_rfc3339();
+
+    // A relative directory would put each workstation's archives wherever it was started
+    let dir = PathBuf::from(paths::normalize_path(&cfg.archive_directory));
+    if !dir.is_absolute() {
+        return Err(format!("activity.archive_directory must be an absolute or mapped shared path, not {}", dir.display()));
+    }
+
+    let max_id: Option<i64> = conn.query_row(
+        "SELECT MAX(id) FROM user_activity WHERE timestamp < ?",
+        params![cutoff],
+        |row| row.get(0)
+    ).map_err(|e| e.to_string())?;
+    let max_id = match max_id {
+        Some(max_id) => max_id,
+        None => return Ok(None),
+    };
+
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let path = dir.join(format!(
+        "activity-before-{}-{}.{}.gz",
+        &cutoff[..10],
+        Local::now().format("%Y%m%d-%H%M%S"),
+        extension(cfg.archive_format)
+    ));
+    let rows = write_archive(conn, &cutoff, max_id, cfg.archive_format, &path)?;
+
+    let machine = paths::get_machine_name();
+    let path_text = path.to_string_lossy().to_string();
+    let now = Utc::now().to_rfc3339();
+    let committed = (|| {
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        // Another workstation may have archived some of these rows while this one was writing
+        let remaining: i64 = tx.query_row(
+            "SELECT COUNT(*) FROM user_activity WHERE id <= ? AND timestamp < ?",
+            params![max_id, cutoff],
+            |row| row.get(0)
+        )?;
+        if remaining as usize != rows {
+            return Ok(None);
+        }
+        tx.execute("DELETE FROM user_activity WHERE id <= ? AND timestamp < ?", params![max_id, cutoff])?;
+        tx.execute(
+            "INSERT INTO activity_archives (machine, user_id, cutoff, rows, path, created_at) VALUES (?, ?, ?, ?, ?, ?)",
+            params![machine, user_id, cutoff, rows as i64, paths::canonical_path(&path_text), now]
+        )?;
+        let id = tx.last_insert_rowid();
+        tx.commit()?;
+        Ok::<Option<i64>, rusqlite::Error>(Some(id))
+    })();
+    let id = match committed {
+        Ok(Some(id)) => id,
+        Ok(None) => {
+            let _ = fs::remove_file(&path);
+            logger::info(&format!("Activity older than {} was archived by another workstation meanwhile", cutoff));
+            return Ok(None);
+        },
+        Err(e) => {
+            // The rows are still in the database, so the archive would duplicate them next run
+            let _ = fs::remove_file(&path);
+            return Err(format!("Failed to remove archived activity: {}", e));
+        },
+    };
+
+    logger::info(&format!("Archived {} activity rows older than {} to {}", rows, cutoff, path_text));
+    Ok(Some(ActivityArchive { id, machine, user_id, cutoff, rows: rows as i64, path: path_text, created_at: now }))
+}
+
+// Scheduled runs are shared by all workstations: skip if any of them archived recently
+fn archive_if_due(cfg: &ActivityConfig) -> Result<(), String> {
+    let mut conn = db::get_connection().map_err(|e| e.to_string())?;
+    let last_run: Option<String> = conn.query_row("SELECT MAX(created_at) FROM activity_archives", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    let due = last_run
+        .and_then(|time| DateTime::parse_from_rfc3339(&time).ok())
+        .map(|time| Utc::now() - time.with_timezone(&Utc) >= Duration::hours(cfg.retention_interval_hours as i64))
+        .unwrap_or(true);
+    if due && archive_older_than(&mut conn, cfg.retention_days, None)?.is_none() {
+        logger::debug(&format!("No activity older than {} days to archive", cfg.retention_days));
+    }
+    Ok(())
+}
+
+// Background retention, started at launch. Reads the configuration on every pass so a
+// reload takes effect without a restart.
+pub fn retention_loop() {
+    loop {
+        let cfg = config::get_config().activity.clone();
+        if cfg.retention_days > 0 {
+            if let Err(e) = archive_if_due(&cfg) {
+                logger::warn(&format!("Activity retention failed: {}", e));
+            }
+        }
+        std::thread::sleep(RETENTION_CHECK);
+    }
+}
+
+// Archive and remove activity now, older than `older_than_days` or the configured retention
+#[tauri::command]
+pub fn archive_activity(older_than_days: Option<u64>, acting_user_id: i64) -> Result<Option<ActivityArchive>, String> {
+    let mut conn = db::get_connection().map_err(|e| e.to_string())?;
+    if !access::is_admin(&conn, acting_user_id) {
+        return Err("Only admins can archive activity".to_string());
+    }
+    let days = older_than_days.unwrap_or(config::get_config().activity.retention_days);
+    if days == 0 {
+        return Err("Give a number of days, or set activity.retention_days".to_string());
+    }
+
+    let archive = archive_older_than(&mut conn, days, Some(acting_user_id))?;
+    if let Some(archive) = &archive {
+        let details = format!("Archived {} rows older than {} to {}", archive.rows, archive.cutoff, archive.path);
+        activity::record_activity(&conn, acting_user_id, ActivityType::ArchiveActivity, None, None, &details);
+    }
+    Ok(archive)
+}
+
+// Past retention runs, newest first
+#[tauri::command]
+pub fn get_activity_archives(acting_user_id: i64) -> Result<Vec<ActivityArchive>, String> {
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    if !access::is_admin(&conn, acting_user_id) {
+        return Err("Only admins can view activity archives".to_string());
+    }
+    let mut stmt = conn.prepare(
+        "SELECT id, machine, user_id, cutoff, rows, path, created_at FROM activity_archives ORDER BY created_at DESC, id DESC"
+    ).map_err(|e| e.to_string())?;
+    let rows = stmt.query_map([], |row| {
+        Ok(ActivityArchive {
+            id: row.get(0)?,
+            machine: row.get(1)?,
+            user_id: row.get(2)?,
+            cutoff: row.get(3)?,
+            rows: row.get(4)?,
+            path: paths::normalize_path(&row.get::<_, String>(5)?),
+            created_at: row.get(6)?,
+        })
+    }).map_err(|e| e.to_string())?;
+    rows.collect::<rusqlite::Result<Vec<ActivityArchive>>>().map_err(|e| e.to_string())
+}
@@ -0,0 +1,626 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::path::Path;
+use serde::{Serialize, Deserialize};
+use rusqlite::{params, Connection, OptionalExtension};
+use chrono::Utc;
+use regex::Regex;
+use once_cell::sync::Lazy;
+use crate::activity::{self, ActivityType};
+use crate::access;
+use crate::db;
+use crate::environment::ResolvedVariable;
+use crate::logger;
+use crate::paths::{self, OsType};
+
+// A DCC installation that can open project files
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Application {
+    pub id: i64,
+    pub name: String,
+    pub version: String,
+    pub windows_path: Option<String>,
+    pub macos_path: Option<String>,
+    pub linux_path: Option<String>,
+    // Arguments passed before the file, e.g. ["--nukex"]
+    pub default_args: Vec<String>,
+    // Extensions handled by this application, without the dot (e.g. ["nk"])
+    pub file_types: Vec<String>,
+}
+
+impl Application {
+    // Executable configured for the OS we are running on
+    pub fn executable_for_current_os(&self) -> Option<&str> {
+        let path = match paths::get_os_type() {
+            OsType::Windows => &self.windows_path,
+            OsType::MacOS => &self.macos_path,
+            OsType::Linux => &self.linux_path,
+            OsType::Unknown => &None,
+        };
+        path.as_deref().filter(|p| !p.trim().is_empty())
+    }
+
+    pub fn handles(&self, file_type: &str) -> bool {
+        self.file_types.iter().any(|t| t.eq_ignore_ascii_case(file_type))
+    }
+
+    pub fn label(&self) -> String {
+        format!("{} {}", self.name, self.version)
+    }
+
+    // Configured for this OS and actually present on disk
+    pub fn is_installed(&self) -> bool {
+        self.executable_for_current_os()
+            .map(|exe| Path::new(&paths::normalize_path(exe)).exists())
+            .unwrap_or(false)
+    }
+}
+
+// A project (or, with `shot_name`, a single shot) locked to one application version
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ApplicationPin {
+    pub id: i64,
+    pub project_id: i64,
+    pub shot_name: Option<String>,
+    pub application_name: String,
+    pub version: String,
+    pub created_at: String,
+}
+
+// A named way of launching every version of an application, e.g. "NukeX" or "Render-only"
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LaunchVariant {
+    pub id: i64, // 0 for a new entry
+    pub application_name: String,
+    pub name: String,
+    // Argument template, e.g. ["-x", "-F", "{first}-{last}", "{file}"]
+    pub args: Vec<String>,
+}
+
+// Tokens available in argument templates, and the launch environment variable each comes from
+const ARGUMENT_TOKENS: &[(&str, &str)] = &[
+    ("first", "FIRST_FRAME"),
+    ("last", "LAST_FRAME"),
+    ("project", "PROJECT"),
+    ("project_path", "PROJECT_PATH"),
+    ("seq", "SEQ"),
+    ("shot", "SHOT"),
+    ("task", "TASK"),
+    ("version", "VERSION"),
+];
+
+// Variants created for every application that opens Nuke scripts
+const NUKE_VARIANTS: &[(&str, &[&str])] = &[
+    ("NukeX", &["--nukex", "{file}"]),
+    ("Nuke", &["{file}"]),
+    ("Nuke Indie", &["--indie", "{file}"]),
+    ("NukeStudio", &["--studio", "{file}"]),
+    ("Nuke Safe Mode", &["--nukex", "--safe", "{file}"]),
+    ("Render-only", &["-x", "-F", "{first}-{last}", "{file}"]),
+];
+
+fn split_list(value: &str) -> Vec<String> {
+    value.split(',').map(|s| s.trim().trim_start_matches('.').to_lowercase()).filter(|s| !s.is_empty()).collect()
+}
+
+fn map_application(row: &rusqlite::Row) -> rusqlite::Result<Application> {
+    let args: Option<String> = row.get(6)?;
+    let file_types: String = row.get(7)?;
+    Ok(Application {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        version: row.get(2)?,
+        windows_path: row.get(3)?,
+        macos_path: row.get(4)?,
+        linux_path: row.get(5)?,
+        default_args: args.and_then(|a| serde_json::from_str(&a).ok()).unwrap_or_default(),
+        file_types: split_list(&file_types),
+    })
+}
+
+const SELECT_APPLICATIONS: &str =
+    "SELECT id, name, version, windows_path, macos_path, linux_path, default_args, file_types FROM applications";
+
+pub fn load_applications(conn: &Connection) -> Result<Vec<Application>, String> {
+    let mut stmt = conn.prepare(&format!("{} ORDER BY name ASC, version DESC", SELECT_APPLICATIONS))
+        .map_err(|e| e.to_string())?;
+    let apps = stmt.query_map([], map_application)
+        .map_err(|e| e.to_string())?
+        .collect::<rusqlite::Result<Vec<Application>>>()
+        .map_err(|e| e.to_string())?;
+    Ok(apps)
+}
+
+static VERSION_NUMBER: Lazy<Regex> = Lazy::new(|| Regex::new(r"\d+").unwrap());
+
+// Compare version strings like "13.2v4" and "15.0v1" by their numeric parts
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let parts = |v: &str| -> Vec<u64> {
+        VERSION_NUMBER.find_iter(v).filter_map(|m| m.as_str().parse().ok()).collect()
+    };
+    parts(a).cmp(&parts(b)).then_with(|| a.cmp(b))
+}
+
+// Pick the application that opens `file_type`: the requested version if given, otherwise the
+// newest version installed on this machine
+pub fn resolve_application(conn: &Connection, file_type: &str, version: Option<&str>) -> Result<Application, String> {
+    let mut candidates: Vec<Application> = load_applications(conn)?
+        .into_iter()
+        .filter(|app| app.handles(file_type))
+        .collect();
+
+    if candidates.is_empty() {
+        return Err(format!("No application is registered for .{} files", file_type));
+    }
+
+    if let Some(version) = version {
+        return candidates.into_iter()
+            .find(|app| app.version == version)
+            .filter(|app| app.executable_for_current_os().is_some())
+            .ok_or_else(|| format!("No application for .{} files with version {} is configured on this machine", file_type, version));
+    }
+
+    candidates.retain(|app| app.executable_for_current_os().is_some());
+    candidates.sort_by(|a, b| compare_versions(&b.version, &a.version));
+    candidates.into_iter()
+        .next()
+        .ok_or_else(|| format!("No application for .{} files has an executable configured for this OS", file_type))
+}
+
+fn map_pin(row: &rusqlite::Row) -> rusqlite::Result<ApplicationPin> {
+    let shot_name: String = row.get(2)?;
+    Ok(ApplicationPin {
+        id: row.get(0)?,
+        project_id: row.get(1)?,
+        shot_name: Some(shot_name).filter(|s| !s.is_empty()),
+        application_name: row.get(3)?,
+        version: row.get(4)?,
+        created_at: row.get(5)?,
+    })
+}
+
+// The pin that applies to a shot: its own pin if it has one, otherwise the project's
+fn find_pin(conn: &Connection, project_id: i64, shot_name: Option<&str>, application_name: &str) -> Result<Option<ApplicationPin>, String> {
+    conn.query_row(
+        "SELECT id, project_id, shot_name, application_name, version, created_at FROM application_pins
+         WHERE project_id = ? AND application_name = ? AND shot_name IN (?, '')
+         ORDER BY shot_name DESC LIMIT 1",
+        params![project_id, application_name, shot_name.unwrap_or("")],
+        map_pin
+    ).optional().map_err(|e| e.to_string())
+}
+
+// The pin that governs launching `file_type` files in a project, or in one shot of it
+pub fn launch_pin(conn: &Connection, file_type: &str, project_id: i64, shot_name: Option<&str>) -> Result<Option<ApplicationPin>, String> {
+    let mut names: Vec<String> = load_applications(conn)?
+        .into_iter()
+        .filter(|app| app.handles(file_type))
+        .map(|app| app.name)
+        .collect();
+    names.sort();
+    names.dedup();
+
+    for name in names {
+        if let Some(pin) = find_pin(conn, project_id, shot_name, &name)? {
+            return Ok(Some(pin));
+        }
+    }
+    Ok(None)
+}
+
+// "Shot sh010" or "This project", for pin errors
+pub fn pin_scope(pin: &ApplicationPin) -> String {
+    match &pin.shot_name {
+        Some(shot) => format!("Shot {}", shot),
+        None => "This project".to_string(),
+    }
+}
+
+// Resolve the application to launch a file with, honouring project and shot pins. A pinned
+// version is mandatory: the launch fails if it is not installed here rather than falling back.
+pub fn resolve_launch_application(
+    conn: &Connection,
+    file_type: &str,
+    project_id: Option<i64>,
+    shot_name: Option<&str>,
+    requested_version: Option<&str>
+) -> Result<Application, String> {
+    let pin = match project_id {
+        Some(project_id) => launch_pin(conn, file_type, project_id, shot_name)?,
+        None => None,
+    };
+    let pin = match pin {
+        Some(pin) => pin,
+        None => return resolve_application(conn, file_type, requested_version),
+    };
+    let scope = pin_scope(&pin);
+
+    if let Some(version) = requested_version {
+        if version != pin.version {
+            return Err(format!("{} is pinned to {} {}; version {} cannot be used", scope, pin.application_name, pin.version, version));
+        }
+    }
+
+    let app = load_applications(conn)?
+        .into_iter()
+        .find(|app| app.name == pin.application_name && app.version == pin.version && app.handles(file_type))
+        .ok_or_else(|| format!("{} is pinned to {} {}, which is not in the application registry", scope, pin.application_name, pin.version))?;
+    if !app.is_installed() {
+        return Err(format!("{} is pinned to {} {}, which is not installed on this machine", scope, pin.application_name, pin.version));
+    }
+
+    logger::info(&format!("Using pinned application {} for project {}", app.label(), pin.project_id));
+    Ok(app)
+}
+
+fn map_variant(row: &rusqlite::Row) -> rusqlite::Result<LaunchVariant> {
+    let args: String = row.get(3)?;
+    Ok(LaunchVariant {
+        id: row.get(0)?,
+        application_name: row.get(1)?,
+        name: row.get(2)?,
+        args: serde_json::from_str(&args).unwrap_or_default(),
+    })
+}
+
+// Argument template for launching `app`, from the named variant or the application's defaults
+pub fn argument_template(conn: &Connection, app: &Application, variant: Option<&str>) -> Result<Vec<String>, String> {
+    let variant = match variant.filter(|v| !v.trim().is_empty()) {
+        Some(variant) => variant,
+        None => return Ok(app.default_args.clone()),
+    };
+    conn.query_row(
+        "SELECT id, application_name, name, args FROM launch_variants WHERE application_name = ? AND name = ?",
+        params![app.name, variant],
+        map_variant
+    ).optional()
+        .map_err(|e| e.to_string())?
+        .map(|v| v.args)
+        .ok_or_else(|| format!("{} has no launch variant named {}", app.name, variant))
+}
+
+// Token values for a launch, taken from the file and its resolved launch environment
+pub fn argument_tokens(file_path: &str, environment: &[ResolvedVariable]) -> HashMap<String, String> {
+    let mut tokens = HashMap::new();
+    tokens.insert("file".to_string(), file_path.to_string());
+    for (token, variable) in ARGUMENT_TOKENS {
+        if let Some(var) = environment.iter().find(|v| v.name == *variable) {
+            tokens.insert(token.to_string(), var.value.clone());
+        }
+    }
+    tokens
+}
+
+// Substitute `{token}`s in an argument template. Templates without `{file}` get the file appended,
+// so plain flag lists such as ["--nukex"] keep working.
+pub fn expand_arguments(template: &[String], tokens: &HashMap<String, String>) -> Result<Vec<String>, String> {
+    let token_regex = Regex::new(r"\{([a-z_]+)\}").unwrap();
+    let mut args = Vec::new();
+    for arg in template {
+        let mut error = None;
+        let expanded = token_regex.replace_all(arg, |caps: &regex::Captures| {
+            let name = &caps[1];
+            match tokens.get(name) {
+                Some(value) => value.clone(),
+                None => {
+                    let known = name == "file" || ARGUMENT_TOKENS.iter().any(|(token, _)| *token == name);
+                    error.get_or_insert(if known {
+                        format!("{{{}}} is not available for this file", name)
+                    } else {
+                        format!("Unknown argument token {{{}}}", name)
+                    });
+                    String::new()
+                }
+            }
+        }).to_string();
+        if let Some(error) = error {
+            return Err(error);
+        }
+        args.push(expanded);
+    }
+
+    if !template.iter().any(|arg| arg.contains("{file}")) {
+        if let Some(file) = tokens.get("file") {
+            args.push(file.clone());
+        }
+    }
+    Ok(args)
+}
+
+// The registry decides which executables every workstation runs, so only admins change it
+fn require_admin(conn: &Connection, acting_user_id: i64) -> Result<(), String> {
+    if access::is_admin(conn, acting_user_id) {
+        Ok(())
+    } else {
+        Err("Only admins can change the application registry".to_string())
+    }
+}
+
+// Whether any registered application opens `file_type`; others go to the OS file associations
+pub fn is_registered_type(conn: &Connection, file_type: &str) -> Result<bool, String> {
+    Ok(load_applications(conn)?.iter().any(|app| app.handles(file_type)))
+}
+
+#[tauri::command]
+pub fn get_applications() -> Result<Vec<Application>, String> {
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    load_applications(&conn)
+}
+
+// Insert a new application (id 0) or update an existing one; returns its id
+#[tauri::command]
+pub fn save_application(application: Application, acting_user_id: i64) -> Result<i64, String> {
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    require_admin(&conn, acting_user_id)?;
+    if application.name.trim().is_empty() || application.version.trim().is_empty() {
+        return Err("Application name and version are required".to_string());
+    }
+    if application.file_types.is_empty() {
+        return Err("Application must handle at least one file type".to_string());
+    }
+
+    let args = serde_json::to_string(&application.default_args).map_err(|e| e.to_string())?;
+    let file_types = split_list(&application.file_types.join(",")).join(",");
+    let now = Utc::now().to_rfc3339();
+
+    if application.id > 0 {
+        conn.execute(
+            "UPDATE applications SET name = ?, version = ?, windows_path = ?, macos_path = ?, linux_path = ?, default_args = ?, file_types = ?, updated_at = ?
+             WHERE id = ?",
+            params![application.name, application.version, application.windows_path, application.macos_path,
+                    application.linux_path, args, file_types, now, application.id],
+        ).map_err(|e| e.to_string())?;
+        ensure_standard_variants(&conn, &application)?;
+        logger::info(&format!("Updated application {}", application.label()));
+        Ok(application.id)
+    } else {
+        conn.execute(
+            "INSERT INTO applications (name, version, windows_path, macos_path, linux_path, default_args, file_types, created_at, updated_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            params![application.name, application.version, application.windows_path, application.macos_path,
+                    application.linux_path, args, file_types, now, now],
+        ).map_err(|e| e.to_string())?;
+        let id = conn.last_insert_rowid();
+        ensure_standard_variants(&conn, &application)?;
+        logger::info(&format!("Registered application {}", application.label()));
+        Ok(id)
+    }
+}
+
+#[tauri::command]
+pub fn delete_application(id: i64, acting_user_id: i64) -> Result<bool, String> {
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    require_admin(&conn, acting_user_id)?;
+    let removed = conn.execute("DELETE FROM applications WHERE id = ?", params![id])
+        .map_err(|e| e.to_string())?;
+    Ok(removed > 0)
+}
+
+#[tauri::command]
+pub fn get_application_pins(project_id: i64, user_id: Option<i64>) -> Result<Vec<ApplicationPin>, String> {
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    access::require_project_access(&conn, project_id, user_id)?;
+
+    let mut stmt = conn.prepare(
+        "SELECT id, project_id, shot_name, application_name, version, created_at FROM application_pins
+         WHERE project_id = ? ORDER BY shot_name ASC, application_name ASC"
+    ).map_err(|e| e.to_string())?;
+    let pins = stmt.query_map(params![project_id], map_pin)
+        .map_err(|e| e.to_string())?
+        .collect::<rusqlite::Result<Vec<ApplicationPin>>>()
+        .map_err(|e| e.to_string())?;
+    Ok(pins)
+}
+
+// Pin a project, or one shot of it when `shot_name` is given, to an application version
+#[tauri::command]
+pub fn set_application_pin(
+    project_id: i64,
+    shot_name: Option<String>,
+    application_name: String,
+    version: String,
+    acting_user_id: i64
+) -> Result<i64, String> {
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    access::require_project_owner(&conn, project_id, acting_user_id)?;
+
+    let registered: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM applications WHERE name = ? AND version = ?)",
+        params![application_name, version],
+        |row| row.get(0)
+    ).map_err(|e| e.to_string())?;
+    if !registered {
+        return Err(format!("{} {} is not in the application registry", application_name, version));
+    }
+
+    let shot = shot_name.map(|s| s.trim().to_string()).unwrap_or_default();
+    let now = Utc::now().to_rfc3339();
+    conn.execute(
+        "INSERT INTO application_pins (project_id, shot_name, application_name, version, created_at) VALUES (?, ?, ?, ?, ?)
+         ON CONFLICT(project_id, shot_name, application_name) DO UPDATE SET version = excluded.version, created_at = excluded.created_at",
+        params![project_id, shot, application_name, version, now],
+    ).map_err(|e| e.to_string())?;
+
+    let id: i64 = conn.query_row(
+        "SELECT id FROM application_pins WHERE project_id = ? AND shot_name = ? AND application_name = ?",
+        params![project_id, shot, application_name],
+        |row| row.get(0)
+    ).map_err(|e| e.to_string())?;
+
+    let target = if shot.is_empty() { "project".to_string() } else { format!("shot {}", shot) };
+    let details = format!("Pinned {} to {} {}", target, application_name, version);
+    activity::record_activity(&conn, acting_user_id, ActivityType::PinApplication, Some(project_id), None, &details);
+    logger::info(&format!("Project {}: {}", project_id, details));
+
+    Ok(id)
+}
+
+#[tauri::command]
+pub fn remove_application_pin(pin_id: i64, acting_user_id: i64) -> Result<bool, String> {
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    let pin = conn.query_row(
+        "SELECT id, project_id, shot_name, application_name, version, created_at FROM application_pins WHERE id = ?",
+        params![pin_id],
+        map_pin
+    ).optional().map_err(|e| e.to_string())?;
+
+    let pin = match pin {
+        Some(pin) => pin,
+        None => return Ok(false),
+    };
+    access::require_project_owner(&conn, pin.project_id, acting_user_id)?;
+
+    conn.execute("DELETE FROM application_pins WHERE id = ?", params![pin_id])
+        .map_err(|e| e.to_string())?;
+
+    let details = format!("Unpinned {} {}", pin.application_name, pin.version);
+    activity::record_activity(&conn, acting_user_id, ActivityType::UnpinApplication, Some(pin.project_id), None, &details);
+    Ok(true)
+}
+
+#[tauri::command]
+pub fn get_launch_variants(application_name: Option<String>) -> Result<Vec<LaunchVariant>, String> {
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    let mut stmt = conn.prepare(
+        "SELECT id, application_name, name, args FROM launch_variants
+         WHERE ?1 IS NULL OR application_name = ?1
+         ORDER BY application_name ASC, id ASC"
+    ).map_err(|e| e.to_string())?;
+    let variants = stmt.query_map(params![application_name], map_variant)
+        .map_err(|e| e.to_string())?
+        .collect::<rusqlite::Result<Vec<LaunchVariant>>>()
+        .map_err(|e| e.to_string())?;
+    Ok(variants)
+}
+
+// Insert a new launch variant (id 0) or update an existing one; returns its id
+#[tauri::command]
+pub fn save_launch_variant(variant: LaunchVariant, acting_user_id: i64) -> Result<i64, String> {
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    require_admin(&conn, acting_user_id)?;
+    if variant.application_name.trim().is_empty() || variant.name.trim().is_empty() {
+        return Err("Launch variant needs an application and a name".to_string());
+    }
+    // Catch typos in the template now rather than at launch time
+    let mut tokens: HashMap<String, String> = ARGUMENT_TOKENS.iter().map(|(token, _)| (token.to_string(), String::new())).collect();
+    tokens.insert("file".to_string(), String::new());
+    expand_arguments(&variant.args, &tokens)?;
+
+    let args = serde_json::to_string(&variant.args).map_err(|e| e.to_string())?;
+    if variant.id > 0 {
+        conn.execute(
+            "UPDATE launch_variants SET application_name = ?, name = ?, args = ? WHERE id = ?",
+            params![variant.application_name, variant.name, args, variant.id],
+        ).map_err(|e| e.to_string())?;
+        Ok(variant.id)
+    } else {
+        conn.execute(
+            "INSERT INTO launch_variants (application_name, name, args, created_at) VALUES (?, ?, ?, ?)",
+            params![variant.application_name, variant.name, args, Utc::now().to_rfc3339()],
+        ).map_err(|e| e.to_string())?;
+        logger::info(&format!("Added launch variant {} for {}", variant.name, variant.application_name));
+        Ok(conn.last_insert_rowid())
+    }
+}
+
+#[tauri::command]
+pub fn delete_launch_variant(id: i64, acting_user_id: i64) -> Result<bool, String> {
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    require_admin(&conn, acting_user_id)?;
+    let removed = conn.execute("DELETE FROM launch_variants WHERE id = ?", params![id])
+        .map_err(|e| e.to_string())?;
+    Ok(removed > 0)
+}
+
+// Add the standard Nuke variants for an application that opens Nuke scripts, keeping any
+// variants of the same name that were already edited
+fn ensure_standard_variants(conn: &Connection, app: &Application) -> Result<(), String> {
+    if !app.handles("nk") {
+        return Ok(());
+    }
+    let now = Utc::now().to_rfc3339();
+    for (variant, args) in NUKE_VARIANTS {
+        let args = serde_json::to_string(args).map_err(|e| e.to_string())?;
+        conn.execute(
+            "INSERT OR IGNORE INTO launch_variants (application_name, name, args, created_at) VALUES (?, ?, ?, ?)",
+            params![app.name, variant, args, now],
+        ).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+// Migration: create the standard Nuke variants for applications registered before variants
+// existed. Applications saved later get them from save_application.
+pub fn seed_launch_variants(conn: &Connection) -> Result<(), String> {
+    for app in load_applications(conn)? {
+        ensure_standard_variants(conn, &app)?;
+    }
+    Ok(())
+}
+
+// Argument template for an executable outside the registry: NukeX for Nuke scripts, as the
+// standard variant list starts with it, and just the file otherwise
+pub fn default_template(file_type: &str) -> Vec<String> {
+    let args: &[&str] = if file_type.eq_ignore_ascii_case("nk") { NUKE_VARIANTS[0].1 } else { &["{file}"] };
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+// The registered application for `file_type` whose executable on this OS is `path`
+pub fn application_for_executable(conn: &Connection, path: &str, file_type: &str) -> Result<Option<Application>, String> {
+    let wanted = paths::normalize_path(path);
+    Ok(load_applications(conn)?
+        .into_iter()
+        .filter(|app| app.handles(file_type))
+        .find(|app| app.executable_for_current_os().map(paths::normalize_path).as_deref() == Some(wanted.as_str())))
+}
+
+// Guess the version from an install path such as ".../Nuke15.1v3/Nuke15.1" or
+// ".../Adobe After Effects 2024/..."
+fn version_from_path(path: &str) -> String {
+    let re = Regex::new(r"(?i)(?:nuke|after effects)\s*(\d+(?:\.\d+)*(?:v\d+)?)").unwrap();
+    re.captures(path)
+        .and_then(|caps| caps.get(1))
+        .map(|m| m.as_str().to_string())
+        .unwrap_or_else(|| "default".to_string())
+}
+
+// Migration: turn the legacy `nuke_executable_path` / `ae_executable_path` settings into registry entries
+pub fn migrate_settings_to_applications(conn: &Connection) -> Result<(), String> {
+    let (nuke, ae): (Option<String>, Option<String>) = conn.query_row(
+        "SELECT nuke_executable_path, ae_executable_path FROM settings WHERE id = 1",
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?))
+    ).optional().map_err(|e| e.to_string())?.unwrap_or((None, None));
+
+    let legacy = [
+        ("Nuke", nuke, vec!["--nukex".to_string()], "nk"),
+        ("After Effects", ae, Vec::new(), "aep"),
+    ];
+
+    let now = Utc::now().to_rfc3339();
+    for (name, path, args, file_type) in legacy {
+        let path = match path.filter(|p| !p.trim().is_empty()) {
+            Some(path) => path,
+            None => continue,
+        };
+
+        // The setting holds whatever OS the user saved it from
+        let (windows, macos, linux) = if path.contains('\\') || path.to_lowercase().ends_with(".exe") || path.chars().nth(1) == Some(':') {
+            (Some(path.clone()), None, None)
+        } else if path.starts_with("/Applications") || path.contains(".app") {
+            (None, Some(path.clone()), None)
+        } else {
+            (None, None, Some(path.clone()))
+        };
+
+        let args = serde_json::to_string(&args).map_err(|e| e.to_string())?;
+        conn.execute(
+            "INSERT OR IGNORE INTO applications (name, version, windows_path, macos_path, linux_path, default_args, file_types, created_at, updated_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            params![name, version_from_path(&path), windows, macos, linux, args, file_type, now, now],
+        ).map_err(|e| format!("Failed to migrate {} path: {}", name, e))?;
+        logger::info(&format!("Migrated {} executable setting into application registry", name));
+    }
+
+    Ok(())
+}
@@ -1,11 +1,25 @@
+use std::collections::{BTreeMap, HashSet};
 use std::fs;
-use std::path::Path;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use once_cell::sync::Lazy;
 use serde::{Serialize, Deserialize};
+use toml::value::Table;
+use crate::access;
+use crate::db;
 use crate::logger;
-use std::sync::OnceLock;
+use crate::redact;
 
-// Static configuration that gets loaded once
-static CONFIG: OnceLock<Config> = OnceLock::new();
+// The loaded configuration, replaced as a whole by reload_config
+static STATE: Lazy<RwLock<ConfigState>> = Lazy::new(|| RwLock::new(load_layers()));
+
+struct ConfigState {
+    config: Arc<Config>,
+    // Layer each dotted key came from
+    sources: BTreeMap<String, String>,
+    // Problems found while loading, shown on the settings page
+    errors: Vec<String>,
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct NetworkConfig {
@@ -13,9 +27,18 @@ pub struct NetworkConfig {
     pub server_port: u16,
 }
 
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DatabaseMode {
+    // The shared database on the network share
+    Network,
+    // A database next to the application, for working offline
+    Local,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct DatabaseConfig {
-    pub mode: String,
+    pub mode: DatabaseMode,
     pub network_path: String, 
     pub windows_drive: String,
 }
@@ -26,11 +49,292 @@ pub struct PathsConfig {
     pub windows_mapped_drive: String,
 }
 
+// One storage root as seen from each OS, e.g. a share mounted as U: on Windows,
+// /Volumes/Naboo on macOS and /mnt/naboo on Linux. Rules are tried in order.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PathMapping {
+    pub name: String,
+    #[serde(default)]
+    pub windows: Option<String>,
+    #[serde(default)]
+    pub macos: Option<String>,
+    #[serde(default)]
+    pub linux: Option<String>,
+    // UNC form of the share, e.g. "//192.168.100.9/Naboo"
+    #[serde(default)]
+    pub unc: Option<String>,
+}
+
+// What opening a file checked out by someone else does
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LockPolicy {
+    Warn,
+    Block,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LocksConfig {
+    pub policy: LockPolicy,
+    // Locks without a heartbeat for this long are considered abandoned
+    pub expiry_minutes: i64,
+    // Write a `<file>.lock` sidecar next to checked out scripts
+    pub write_sidecar: bool,
+    // Check a file out while a launched application has it open
+    #[serde(default = "default_checkout_on_open")]
+    pub checkout_on_open: bool,
+}
+
+fn default_checkout_on_open() -> bool {
+    true
+}
+
+impl Default for LocksConfig {
+    fn default() -> Self {
+        LocksConfig {
+            policy: LockPolicy::Warn,
+            expiry_minutes: 15,
+            write_sidecar: true,
+            checkout_on_open: true,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SessionLogsConfig {
+    // Where per-launch DCC output is written; empty for the platform data directory. A shared
+    // path lets support read other machines' logs.
+    pub directory: String,
+    // Number of session logs kept on this machine
+    pub keep_sessions: usize,
+    // Session logs older than this are removed regardless of count
+    pub max_age_days: u64,
+}
+
+impl Default for SessionLogsConfig {
+    fn default() -> Self {
+        SessionLogsConfig {
+            directory: String::new(),
+            keep_sessions: 200,
+            max_age_days: 30,
+        }
+    }
+}
+
+// How a project is watched for changes
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum WatcherBackend {
+    // Poll projects on network shares, use OS notifications elsewhere
+    Auto,
+    // inotify, FSEvents or ReadDirectoryChangesW
+    Native,
+    // Compare mtime/size snapshots every poll_interval_secs
+    Poll,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WatcherConfig {
+    // Filesystem events are coalesced until the project has been quiet this long
+    pub debounce_ms: u64,
+    #[serde(default = "default_watcher_backend")]
+    pub backend: WatcherBackend,
+    #[serde(default = "default_poll_interval")]
+    pub poll_interval_secs: u64,
+    // How often the shared database is checked for changes indexed by other workstations
+    #[serde(default = "default_change_feed_interval")]
+    pub change_feed_secs: u64,
+    // Limits on concurrent watches; each scan directory of a project counts as a directory
+    #[serde(default = "default_max_watched_projects")]
+    pub max_projects: usize,
+    #[serde(default = "default_max_watched_directories")]
+    pub max_directories: usize,
+}
+
+fn default_watcher_backend() -> WatcherBackend {
+    WatcherBackend::Auto
+}
+
+fn default_poll_interval() -> u64 {
+    10
+}
+
+fn default_change_feed_interval() -> u64 {
+    5
+}
+
+fn default_max_watched_projects() -> usize {
+    20
+}
+
+fn default_max_watched_directories() -> usize {
+    200
+}
+
+impl Default for WatcherConfig {
+    fn default() -> Self {
+        WatcherConfig {
+            debounce_ms: 2000,
+            backend: default_watcher_backend(),
+            poll_interval_secs: default_poll_interval(),
+            change_feed_secs: default_change_feed_interval(),
+            max_projects: default_max_watched_projects(),
+            max_directories: default_max_watched_directories(),
+        }
+    }
+}
+
+// Whether file knobs are remapped through the path mappings when opening a .nk
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum NukeLocalizeMode {
+    Off,
+    // Open a localized copy
+    Copy,
+    // Rewrite the script, keeping a backup
+    InPlace,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct NukePathsConfig {
+    pub on_launch: NukeLocalizeMode,
+    // Where "copy" writes the localized scripts; empty for the platform data directory
+    pub directory: String,
+}
+
+impl Default for NukePathsConfig {
+    fn default() -> Self {
+        NukePathsConfig {
+            on_launch: NukeLocalizeMode::Off,
+            directory: String::new(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    Text,
+    // One JSON object per line, for log shippers
+    Json,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogRotation {
+    // Start a new file each day
+    Daily,
+    // Start a new file past max_size_mb
+    Size,
+    Never,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct LoggingConfig {
+    // error, warn, info, debug or trace
+    pub level: String,
+    // Per-module overrides, e.g. watcher = "debug"
+    pub modules: BTreeMap<String, String>,
+    // Empty for the platform data directory; relative paths are taken from there
+    pub directory: String,
+    pub format: LogFormat,
+    pub rotation: LogRotation,
+    pub max_size_mb: u64,
+    // Rotated files kept besides the current one
+    pub keep_files: usize,
+    // Echo log lines to stdout/stderr
+    pub console: bool,
+    // Extra regexes masked in every log line, on top of passwords, tokens and bcrypt hashes
+    pub redact: Vec<String>,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        LoggingConfig {
+            level: "info".to_string(),
+            modules: BTreeMap::new(),
+            directory: String::new(),
+            format: LogFormat::Text,
+            rotation: LogRotation::Daily,
+            max_size_mb: 10,
+            keep_files: 14,
+            console: true,
+            redact: Vec::new(),
+        }
+    }
+}
+
+// File format for activity exports and retention archives
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct ActivityConfig {
+    // Activity older than this many days is archived and removed; 0 keeps everything
+    pub retention_days: u64,
+    // Where archives are written: an absolute or mapped shared path, so every workstation's
+    // archives end up together
+    pub archive_directory: String,
+    pub archive_format: ExportFormat,
+    // Minimum hours between scheduled archive runs, across all workstations
+    pub retention_interval_hours: u64,
+}
+
+impl Default for ActivityConfig {
+    fn default() -> Self {
+        ActivityConfig {
+            retention_days: 0,
+            archive_directory: "{network}/activity_archive".to_string(),
+            archive_format: ExportFormat::Csv,
+            retention_interval_hours: 24,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct VersionsConfig {
+    // Regex for the artist initials segment before a version, e.g. "NP" in comp_NP_v003.
+    // Only segments matching it are treated as initials; matched case-sensitively so
+    // lower-case task names such as "fx" or "cg" stay part of the name.
+    pub initials_pattern: String,
+}
+
+impl Default for VersionsConfig {
+    fn default() -> Self {
+        VersionsConfig {
+            initials_pattern: "[A-Z]{2,3}".to_string(),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Config {
     pub network: NetworkConfig,
     pub database: DatabaseConfig,
     pub paths: PathsConfig,
+    #[serde(default)]
+    pub locks: LocksConfig,
+    #[serde(default)]
+    pub session_logs: SessionLogsConfig,
+    #[serde(default)]
+    pub path_mappings: Vec<PathMapping>,
+    #[serde(default)]
+    pub nuke_paths: NukePathsConfig,
+    #[serde(default)]
+    pub watcher: WatcherConfig,
+    #[serde(default)]
+    pub logging: LoggingConfig,
+    #[serde(default)]
+    pub activity: ActivityConfig,
+    #[serde(default)]
+    pub versions: VersionsConfig,
 }
 
 impl Default for Config {
@@ -41,7 +345,7 @@ impl Default for Config {
                 server_port: 8080,
             },
             database: DatabaseConfig {
-                mode: "network".to_string(),
+                mode: DatabaseMode::Network,
                 network_path: "//192.168.100.9/Naboo/DB".to_string(),
                 windows_drive: "U:".to_string(),
             },
@@ -49,54 +353,415 @@ impl Default for Config {
                 network_base: "//192.168.100.9/Naboo".to_string(),
                 windows_mapped_drive: "U:".to_string(),
             },
+            locks: LocksConfig::default(),
+            session_logs: SessionLogsConfig::default(),
+            path_mappings: Vec::new(),
+            nuke_paths: NukePathsConfig::default(),
+            watcher: WatcherConfig::default(),
+            logging: LoggingConfig::default(),
+            activity: ActivityConfig::default(),
+            versions: VersionsConfig::default(),
         }
     }
 }
 
-// Load configuration from file
-pub fn load_config() -> &'static Config {
-    CONFIG.get_or_init(|| {
-        let config_path = Path::new("config.toml");
-        
-        if config_path.exists() {
-            match fs::read_to_string(config_path) {
-                Ok(content) => {
-                    match toml::from_str::<Config>(&content) {
-                        Ok(config) => {
-                            logger::info("Configuration loaded successfully from config.toml");
-                            config
-                        },
-                        Err(e) => {
-                            logger::error(&format!("Error parsing config.toml: {}", e));
-                            Config::default()
-                        }
-                    }
-                },
-                Err(e) => {
-                    logger::error(&format!("Error reading config.toml: {}", e));
-                    Config::default()
-                }
+impl Config {
+    // Problems serde cannot catch, one message per problem
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.network.server_ip.trim().is_empty() {
+            errors.push("network.server_ip is empty".to_string());
+        }
+        if self.database.network_path.trim().is_empty() {
+            errors.push("database.network_path is empty".to_string());
+        }
+        if self.locks.expiry_minutes < 1 {
+            errors.push("locks.expiry_minutes must be at least 1".to_string());
+        }
+        if self.watcher.debounce_ms == 0 {
+            errors.push("watcher.debounce_ms must be at least 1".to_string());
+        }
+        if self.watcher.poll_interval_secs == 0 {
+            errors.push("watcher.poll_interval_secs must be at least 1".to_string());
+        }
+        if self.watcher.change_feed_secs == 0 {
+            errors.push("watcher.change_feed_secs must be at least 1".to_string());
+        }
+        if self.watcher.max_projects == 0 || self.watcher.max_directories == 0 {
+            errors.push("watcher.max_projects and watcher.max_directories must be at least 1".to_string());
+        }
+        if self.session_logs.keep_sessions == 0 {
+            errors.push("session_logs.keep_sessions must be at least 1".to_string());
+        }
+        let levels = std::iter::once(("logging.level".to_string(), &self.logging.level))
+            .chain(self.logging.modules.iter().map(|(module, level)| (format!("logging.modules.{}", module), level)));
+        for (key, level) in levels {
+            if level.parse::<log::LevelFilter>().is_err() {
+                errors.push(format!("{} must be off, error, warn, info, debug or trace, not \"{}\"", key, level));
+            }
+        }
+        for pattern in &self.logging.redact {
+            if let Err(e) = regex::Regex::new(pattern) {
+                errors.push(format!("logging.redact pattern \"{}\" is invalid: {}", pattern, e));
             }
-        } else {
-            logger::warn("config.toml not found, using default configuration");
-            let default_config = Config::default();
-            
-            // Try to write default config for future use
-            match toml::to_string_pretty(&default_config) {
-                Ok(content) => {
-                    let _ = fs::write(config_path, content);
-                },
-                Err(e) => {
-                    logger::error(&format!("Error creating default config.toml: {}", e));
+        }
+        if let Err(e) = regex::Regex::new(&self.versions.initials_pattern) {
+            errors.push(format!("versions.initials_pattern \"{}\" is invalid: {}", self.versions.initials_pattern, e));
+        }
+        if self.logging.rotation == LogRotation::Size && self.logging.max_size_mb == 0 {
+            errors.push("logging.max_size_mb must be at least 1".to_string());
+        }
+        if self.activity.retention_interval_hours == 0 {
+            errors.push("activity.retention_interval_hours must be at least 1".to_string());
+        }
+        if self.activity.retention_days > 0 && self.activity.archive_directory.trim().is_empty() {
+            errors.push("activity.archive_directory is empty but activity.retention_days is set".to_string());
+        }
+
+        // Mapping names end up in stored paths as {name}
+        let mut names = HashSet::new();
+        for mapping in &self.path_mappings {
+            let name = mapping.name.trim();
+            if name.is_empty() || name.contains(['{', '}', '/', '\\']) {
+                errors.push(format!("path_mappings name \"{}\" must be non-empty without braces or slashes", mapping.name));
+            } else if !names.insert(name.to_string()) {
+                errors.push(format!("path_mappings name \"{}\" is used more than once", name));
+            }
+            if [&mapping.windows, &mapping.macos, &mapping.linux, &mapping.unc].iter().all(|root| root.is_none()) {
+                errors.push(format!("path_mappings \"{}\" has no roots", mapping.name));
+            }
+        }
+        errors
+    }
+}
+
+// Where a configuration value came from, lowest precedence first
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ConfigValue {
+    // Dotted key, e.g. "database.mode"
+    pub key: String,
+    pub value: toml::Value,
+    // "default", "system <path>", "working directory <path>", "user <path>", "env VFX_DB_MODE" or "cli --set"
+    pub source: String,
+}
+
+// Environment variables that override single keys
+const ENV_OVERRIDES: &[(&str, &str)] = &[
+    ("VFX_DB_MODE", "database.mode"),
+    ("VFX_DB_PATH", "database.network_path"),
+    ("VFX_NETWORK_BASE", "paths.network_base"),
+    ("VFX_SERVER_IP", "network.server_ip"),
+    ("VFX_SERVER_PORT", "network.server_port"),
+];
+
+// Studio-wide file managed by IT
+fn system_config_path() -> PathBuf {
+    #[cfg(target_os = "windows")]
+    {
+        let base = std::env::var("PROGRAMDATA").unwrap_or_else(|_| "C:\\ProgramData".to_string());
+        PathBuf::from(base).join("VFX Launcher").join("config.toml")
+    }
+    #[cfg(target_os = "macos")]
+    {
+        PathBuf::from("/Library/Application Support/VFX Launcher/config.toml")
+    }
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    {
+        PathBuf::from("/etc/vfx-launcher/config.toml")
+    }
+}
+
+// Per-user overrides, e.g. ~/.config/vfx-launcher/config.toml
+fn user_config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("vfx-launcher").join("config.toml"))
+}
+
+// Files to layer over the defaults, lowest precedence first. config.toml in the working
+// directory is still read for existing installs, and `--config <file>` adds one on top.
+fn config_files(args: &[String]) -> Vec<(String, PathBuf)> {
+    let mut files = vec![
+        ("system".to_string(), system_config_path()),
+        ("working directory".to_string(), PathBuf::from("config.toml")),
+    ];
+    if let Some(path) = user_config_path() {
+        files.push(("user".to_string(), path));
+    }
+    for pair in args.windows(2) {
+        if pair[0] == "--config" {
+            files.push(("cli --config".to_string(), PathBuf::from(&pair[1])));
+        }
+    }
+    files
+}
+
+// Overlay `layer` onto `base`, recording `source` for every leaf it sets. Arrays such as
+// path_mappings are replaced as a whole rather than merged.
+fn merge(base: &mut Table, layer: Table, prefix: &str, source: &str, sources: &mut BTreeMap<String, String>) {
+    for (key, value) in layer {
+        let dotted = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(existing)), toml::Value::Table(table)) => {
+                merge(existing, table, &dotted, source, sources);
+            },
+            (_, value) => {
+                sources.retain(|k, _| k != &dotted && !k.starts_with(&format!("{}.", dotted)));
+                sources.insert(dotted, source.to_string());
+                base.insert(key, value);
+            },
+        }
+    }
+}
+
+// Leaves of the defaults, so every key has a source even when no file sets it
+fn record_defaults(table: &Table, prefix: &str, sources: &mut BTreeMap<String, String>) {
+    for (key, value) in table {
+        let dotted = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+        match value {
+            toml::Value::Table(table) => record_defaults(table, &dotted, sources),
+            _ => { sources.insert(dotted, "default".to_string()); },
+        }
+    }
+}
+
+// Set a single dotted key from a string, typed like the value it replaces
+fn set_key(root: &mut Table, dotted: &str, raw: &str, source: &str, sources: &mut BTreeMap<String, String>) -> Result<(), String> {
+    let mut parts: Vec<&str> = dotted.split('.').collect();
+    let leaf = parts.pop().ok_or_else(|| format!("Invalid configuration key {}", dotted))?;
+    let mut table = root;
+    for part in parts {
+        table = match table.entry(part.to_string()).or_insert_with(|| toml::Value::Table(Table::new())) {
+            toml::Value::Table(table) => table,
+            _ => return Err(format!("{} is not a configuration section", part)),
+        };
+    }
+
+    let value = match table.get(leaf) {
+        Some(toml::Value::Integer(_)) => raw.parse::<i64>().map(toml::Value::Integer)
+            .map_err(|_| format!("{} expects a number, got {}", dotted, raw))?,
+        Some(toml::Value::Boolean(_)) => raw.parse::<bool>().map(toml::Value::Boolean)
+            .map_err(|_| format!("{} expects true or false, got {}", dotted, raw))?,
+        _ => toml::Value::String(raw.to_string()),
+    };
+    table.insert(leaf.to_string(), value);
+    sources.insert(dotted.to_string(), source.to_string());
+    Ok(())
+}
+
+// Build the configuration from built-in defaults, the system file, config.toml in the
+// working directory, the user file, VFX_* environment variables and finally
+// `--set section.key=value` flags. Missing files are skipped; nothing is written to disk.
+fn load_layers() -> ConfigState {
+    load_layers_with(None)
+}
+
+// As load_layers, with `user_layer` standing in for the user configuration file, so a change
+// can be checked against every layer before it is written
+fn load_layers_with(user_layer: Option<&Table>) -> ConfigState {
+    let args: Vec<String> = std::env::args().collect();
+    let mut sources = BTreeMap::new();
+    let mut errors = Vec::new();
+    let mut merged = match toml::Value::try_from(Config::default()) {
+        Ok(toml::Value::Table(table)) => table,
+        _ => Table::new(),
+    };
+    record_defaults(&merged, "", &mut sources);
+
+    for (layer, path) in config_files(&args) {
+        let replacement = user_layer.filter(|_| Some(&path) == user_config_path().as_ref());
+        if replacement.is_none() && !path.exists() {
+            continue;
+        }
+        let source = format!("{} {}", layer, path.display());
+        let parsed = match replacement {
+            Some(table) => Ok(table.clone()),
+            None => fs::read_to_string(&path).map_err(|e| e.to_string())
+                .and_then(|content| toml::from_str::<Table>(&content).map_err(|e| e.to_string())),
+        };
+        match parsed {
+            Ok(table) => {
+                merge(&mut merged, table, "", &source, &mut sources);
+                logger::info(&format!("Configuration loaded from {}", source));
+            },
+            Err(e) => errors.push(format!("Skipped {}: {}", source, e)),
+        }
+    }
+
+    for (var, key) in ENV_OVERRIDES {
+        if let Ok(value) = std::env::var(var) {
+            if let Err(e) = set_key(&mut merged, key, &value, &format!("env {}", var), &mut sources) {
+                errors.push(format!("Ignored {}: {}", var, e));
+            }
+        }
+    }
+
+    for pair in args.windows(2).filter(|pair| pair[0] == "--set") {
+        match pair[1].split_once('=') {
+            Some((key, value)) => {
+                if let Err(e) = set_key(&mut merged, key.trim(), value.trim(), "cli --set", &mut sources) {
+                    errors.push(format!("Ignored --set {}: {}", pair[1], e));
                 }
+            },
+            None => errors.push(format!("Ignored --set {}: expected section.key=value", pair[1])),
+        }
+    }
+
+    let config = match toml::Value::Table(merged).try_into::<Config>() {
+        Ok(config) => {
+            errors.extend(config.validate());
+            config
+        },
+        Err(e) => {
+            errors.push(format!("Invalid configuration, using defaults: {}", e));
+            sources.clear();
+            if let Ok(toml::Value::Table(table)) = toml::Value::try_from(Config::default()) {
+                record_defaults(&table, "", &mut sources);
             }
-            
-            default_config
+            Config::default()
         }
-    })
+    };
+    for error in &errors {
+        logger::error(error);
+    }
+
+    ConfigState { config: Arc::new(config), sources, errors }
+}
+
+// Load the layered configuration
+pub fn load_config() -> Arc<Config> {
+    get_config()
+}
+
+fn lookup<'a>(table: &'a Table, dotted: &str) -> Option<&'a toml::Value> {
+    let mut parts = dotted.split('.');
+    let mut value = table.get(parts.next()?)?;
+    for part in parts {
+        value = value.as_table()?.get(part)?;
+    }
+    Some(value)
+}
+
+fn config_table(config: &Config) -> Result<Table, String> {
+    match toml::Value::try_from(config).map_err(|e| e.to_string())? {
+        toml::Value::Table(table) => Ok(table),
+        _ => Err("Configuration is not a table".to_string()),
+    }
 }
 
 // Get the current configuration
-pub fn get_config() -> &'static Config {
-    load_config()
+pub fn get_config() -> Arc<Config> {
+    match STATE.read() {
+        Ok(state) => state.config.clone(),
+        Err(poisoned) => poisoned.into_inner().config.clone(),
+    }
+}
+
+// The effective configuration with secrets masked, for diagnostics bundles
+pub fn redacted_config() -> Result<toml::Value, String> {
+    let mut value = toml::Value::Table(config_table(&get_config())?);
+    redact::redact_toml("", &mut value);
+    Ok(value)
+}
+
+fn require_admin(acting_user_id: i64) -> Result<(), String> {
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    if access::is_admin(&conn, acting_user_id) {
+        Ok(())
+    } else {
+        Err("Only admins can manage the configuration".to_string())
+    }
+}
+
+// Every effective configuration value with the layer it came from
+#[tauri::command]
+pub fn get_config_sources() -> Result<Vec<ConfigValue>, String> {
+    let state = STATE.read().map_err(|e| e.to_string())?;
+    let table = config_table(&state.config)?;
+
+    Ok(state.sources.iter()
+        .filter_map(|(key, source)| {
+            let value = lookup(&table, key)?.clone();
+            Some(ConfigValue { key: key.clone(), value, source: source.clone() })
+        })
+        .collect())
+}
+
+// Problems found by the last load, e.g. a malformed file that was skipped
+#[tauri::command]
+pub fn get_config_errors() -> Result<Vec<String>, String> {
+    Ok(STATE.read().map_err(|e| e.to_string())?.errors.clone())
+}
+
+// Re-read every layer. A configuration that fails to parse or validate is rejected
+// and the running one kept.
+#[tauri::command]
+pub fn reload_config() -> Result<bool, String> {
+    install(load_layers())
+}
+
+// Make a freshly loaded configuration current, unless loading it found problems
+fn install(state: ConfigState) -> Result<bool, String> {
+    if !state.errors.is_empty() {
+        return Err(state.errors.join("\n"));
+    }
+    logger::configure(&state.config.logging);
+    *STATE.write().map_err(|e| e.to_string())? = state;
+    logger::info("Configuration reloaded");
+    Ok(true)
+}
+
+// Effective values of one section, e.g. "locks" or "path_mappings"
+#[tauri::command]
+pub fn get_config_section(section: String, acting_user_id: i64) -> Result<toml::Value, String> {
+    require_admin(acting_user_id)?;
+    config_table(&get_config())?
+        .remove(&section)
+        .ok_or_else(|| format!("Unknown configuration section {}", section))
+}
+
+// Replace one section in the user config file and reload. The change is checked against
+// the effective configuration first, and the file is replaced atomically through a
+// temporary file so a crash cannot leave it half written.
+#[tauri::command]
+pub fn update_config_section(section: String, values: serde_json::Value, acting_user_id: i64) -> Result<bool, String> {
+    require_admin(acting_user_id)?;
+    let value = toml::Value::try_from(&values).map_err(|e| format!("Invalid values for {}: {}", section, e))?;
+
+    // Validate the section in the context of everything else
+    let mut effective = config_table(&get_config())?;
+    if !effective.contains_key(&section) {
+        return Err(format!("Unknown configuration section {}", section));
+    }
+    effective.insert(section.clone(), value.clone());
+    let candidate = toml::Value::Table(effective).try_into::<Config>().map_err(|e| e.to_string())?;
+    let errors = candidate.validate();
+    if !errors.is_empty() {
+        return Err(errors.join("\n"));
+    }
+
+    let path = user_config_path().ok_or("No user configuration directory on this machine")?;
+    let mut file_table = if path.exists() {
+        let content = fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        toml::from_str::<Table>(&content).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?
+    } else {
+        Table::new()
+    };
+    file_table.insert(section.clone(), value);
+    let content = toml::to_string_pretty(&file_table).map_err(|e| e.to_string())?;
+
+    // Load every layer with the new file in place first, so a change that only fails once
+    // merged (or an env/CLI override it conflicts with) never reaches the disk
+    let state = load_layers_with(Some(&file_table));
+    if !state.errors.is_empty() {
+        return Err(state.errors.join("\n"));
+    }
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let temp = path.with_extension("toml.tmp");
+    fs::write(&temp, content).map_err(|e| format!("Failed to write {}: {}", temp.display(), e))?;
+    fs::rename(&temp, &path).map_err(|e| format!("Failed to replace {}: {}", path.display(), e))?;
+    logger::info(&format!("Configuration section {} updated in {}", section, path.display()));
+
+    install(state)
 }
@@ -1,7 +1,12 @@
-use rusqlite::{Connection, params};
+use rusqlite::{Connection, OptionalExtension, params};
 use serde::{Serialize, Deserialize};
 use chrono::Utc;
 use std::path::PathBuf;
+use crate::access;
+use crate::activity::{self, ActivityType};
+use crate::applications;
+use crate::environment;
+use crate::sessions;
 use crate::logger;
 use crate::paths;
 
@@ -109,6 +114,7 @@ fn init_db_tables(conn: &Connection) -> Result<(), String> {
             shot_name TEXT,
             last_modified TEXT NOT NULL,
             created_at TEXT NOT NULL,
+            missing INTEGER NOT NULL DEFAULT 0,
             FOREIGN KEY(project_id) REFERENCES projects(id) ON DELETE CASCADE
         );
 
@@ -162,6 +168,156 @@ fn init_db_tables(conn: &Connection) -> Result<(), String> {
             FOREIGN KEY(project_id) REFERENCES projects(id) ON DELETE CASCADE,
             UNIQUE(user_id, project_id)
         );
+
+        CREATE TABLE IF NOT EXISTS project_members (
+            id INTEGER PRIMARY KEY,
+            project_id INTEGER NOT NULL,
+            user_id INTEGER NOT NULL,
+            role TEXT NOT NULL CHECK (role IN ('owner', 'member', 'read_only')),
+            created_at TEXT NOT NULL,
+            FOREIGN KEY(project_id) REFERENCES projects(id) ON DELETE CASCADE,
+            FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE CASCADE,
+            UNIQUE(project_id, user_id)
+        );
+
+        CREATE TABLE IF NOT EXISTS file_locks (
+            file_id INTEGER PRIMARY KEY,
+            user_id INTEGER NOT NULL,
+            machine TEXT NOT NULL,
+            locked_at TEXT NOT NULL,
+            heartbeat_at TEXT NOT NULL,
+            sidecar_path TEXT,
+            FOREIGN KEY(file_id) REFERENCES project_files(id) ON DELETE CASCADE,
+            FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS applications (
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            version TEXT NOT NULL,
+            windows_path TEXT,
+            macos_path TEXT,
+            linux_path TEXT,
+            default_args TEXT,
+            file_types TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            UNIQUE(name, version)
+        );
+
+        CREATE TABLE IF NOT EXISTS application_pins (
+            id INTEGER PRIMARY KEY,
+            project_id INTEGER NOT NULL,
+            shot_name TEXT NOT NULL DEFAULT '',
+            application_name TEXT NOT NULL,
+            version TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY(project_id) REFERENCES projects(id) ON DELETE CASCADE,
+            UNIQUE(project_id, shot_name, application_name)
+        );
+
+        CREATE TABLE IF NOT EXISTS launch_variants (
+            id INTEGER PRIMARY KEY,
+            application_name TEXT NOT NULL,
+            name TEXT NOT NULL,
+            args TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            UNIQUE(application_name, name)
+        );
+
+        CREATE TABLE IF NOT EXISTS environment_variables (
+            id INTEGER PRIMARY KEY,
+            scope TEXT NOT NULL CHECK (scope IN ('global', 'project', 'application')),
+            scope_key TEXT NOT NULL DEFAULT '',
+            name TEXT NOT NULL,
+            value TEXT NOT NULL,
+            operation TEXT NOT NULL DEFAULT 'set' CHECK (operation IN ('set', 'prepend', 'append')),
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS shots (
+            id INTEGER PRIMARY KEY,
+            project_id INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            first_frame INTEGER,
+            last_frame INTEGER,
+            format TEXT,
+            plate_path TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            FOREIGN KEY(project_id) REFERENCES projects(id) ON DELETE CASCADE,
+            UNIQUE(project_id, name)
+        );
+
+        CREATE TABLE IF NOT EXISTS script_templates (
+            id INTEGER PRIMARY KEY,
+            project_id INTEGER,
+            file_type TEXT NOT NULL,
+            template_path TEXT NOT NULL,
+            script_pattern TEXT NOT NULL,
+            render_pattern TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            FOREIGN KEY(project_id) REFERENCES projects(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS launch_sessions (
+            id INTEGER PRIMARY KEY,
+            user_id INTEGER,
+            project_id INTEGER,
+            file_id INTEGER,
+            file_path TEXT NOT NULL,
+            application TEXT NOT NULL,
+            machine TEXT NOT NULL,
+            pid INTEGER,
+            status TEXT NOT NULL CHECK (status IN ('running', 'exited', 'crashed', 'lost')),
+            exit_code INTEGER,
+            started_at TEXT NOT NULL,
+            ended_at TEXT,
+            duration_seconds INTEGER,
+            log_path TEXT,
+            FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE SET NULL,
+            FOREIGN KEY(project_id) REFERENCES projects(id) ON DELETE SET NULL,
+            FOREIGN KEY(file_id) REFERENCES project_files(id) ON DELETE SET NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS project_file_events (
+            id INTEGER PRIMARY KEY,
+            project_id INTEGER NOT NULL,
+            machine TEXT NOT NULL,
+            added TEXT NOT NULL,
+            changed TEXT NOT NULL,
+            removed TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY(project_id) REFERENCES projects(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS project_watches (
+            id INTEGER PRIMARY KEY,
+            project_id INTEGER NOT NULL,
+            machine TEXT NOT NULL,
+            user_id INTEGER,
+            scan_dirs TEXT NOT NULL,
+            backend TEXT,
+            created_at TEXT NOT NULL,
+            UNIQUE(project_id, machine),
+            FOREIGN KEY(project_id) REFERENCES projects(id) ON DELETE CASCADE,
+            FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE SET NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_user_activity_timestamp ON user_activity(timestamp);
+
+        CREATE TABLE IF NOT EXISTS activity_archives (
+            id INTEGER PRIMARY KEY,
+            machine TEXT NOT NULL,
+            user_id INTEGER,
+            cutoff TEXT NOT NULL,
+            rows INTEGER NOT NULL,
+            path TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE SET NULL
+        );
         ",
     ).map_err(|e| format!("Failed to create tables: {}", e))?;
     // Insert default settings row if absent
@@ -170,6 +326,125 @@ fn init_db_tables(conn: &Connection) -> Result<(), String> {
         params!["nuke,ae", "*.nk,*.aep", ""],
     ).map_err(|e| format!("Failed to insert default settings: {}", e))?;
     
+    run_migrations(conn)?;
+    
+    Ok(())
+}
+
+type Migration = fn(&Connection) -> Result<(), String>;
+
+// Data migrations, applied in order. `PRAGMA user_version` records how many have run,
+// so append new entries and never reorder existing ones.
+const MIGRATIONS: &[(&str, Migration)] = &[
+    ("move executable settings into application registry", applications::migrate_settings_to_applications),
+    ("add log path to launch sessions", sessions::add_session_log_column),
+    ("create standard Nuke launch variants", applications::seed_launch_variants),
+    ("add format and plate path to shots", environment::add_shot_detail_columns),
+    ("store paths as path mapping tokens", canonicalize_stored_paths),
+    ("flag project files missing on disk", add_missing_column),
+    ("store activity archive paths as path mapping tokens", canonicalize_archive_paths),
+];
+
+// Databases from before incremental watching lack project_files.missing
+fn add_missing_column(conn: &Connection) -> Result<(), String> {
+    let has_column: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM pragma_table_info('project_files') WHERE name = 'missing')",
+        [],
+        |row| row.get(0)
+    ).map_err(|e| e.to_string())?;
+    if !has_column {
+        conn.execute_batch("ALTER TABLE project_files ADD COLUMN missing INTEGER NOT NULL DEFAULT 0")
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+// Columns holding paths, rewritten from whatever OS path the writing machine saw
+// to the canonical {root}/relative form (see paths::canonical_path)
+const PATH_COLUMNS: &[(&str, &str)] = &[
+    ("projects", "path"),
+    ("project_files", "path"),
+    ("shots", "plate_path"),
+    ("script_templates", "template_path"),
+    ("file_locks", "sidecar_path"),
+    ("activity_archives", "path"),
+];
+
+fn canonicalize_stored_paths(conn: &Connection) -> Result<(), String> {
+    canonicalize_columns(conn, PATH_COLUMNS)
+}
+
+// activity_archives.path joined PATH_COLUMNS after databases had already been converted
+fn canonicalize_archive_paths(conn: &Connection) -> Result<(), String> {
+    canonicalize_columns(conn, &[("activity_archives", "path")])
+}
+
+fn canonicalize_columns(conn: &Connection, columns: &[(&str, &str)]) -> Result<(), String> {
+    for (table, column) in columns {
+        let rows: Vec<(i64, String)> = {
+            let mut stmt = conn.prepare(&format!("SELECT rowid, {} FROM {} WHERE {} IS NOT NULL", column, table, column))
+                .map_err(|e| e.to_string())?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+                .map_err(|e| e.to_string())?
+                .collect::<rusqlite::Result<Vec<(i64, String)>>>()
+                .map_err(|e| e.to_string())?;
+            rows
+        };
+
+        let mut converted = 0;
+        for (rowid, path) in rows {
+            let canonical = paths::canonical_path(&path);
+            if canonical != path {
+                conn.execute(&format!("UPDATE {} SET {} = ? WHERE rowid = ?", table, column), params![canonical, rowid])
+                    .map_err(|e| format!("Failed to convert {}.{} {}: {}", table, column, path, e))?;
+                converted += 1;
+            }
+        }
+        logger::info(&format!("Converted {} paths in {}.{}", converted, table, column));
+    }
+    Ok(())
+}
+
+// Current schema version of the database
+pub fn schema_version(conn: &Connection) -> Result<i64, String> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+        .map_err(|e| format!("Failed to read schema version: {}", e))
+}
+
+// Schema version once every migration in this build has run
+pub fn latest_schema_version() -> i64 {
+    MIGRATIONS.len() as i64
+}
+
+fn run_migrations(conn: &Connection) -> Result<(), String> {
+    let current = schema_version(conn)?;
+    
+    for (index, (name, migration)) in MIGRATIONS.iter().enumerate().skip(current.max(0) as usize) {
+        let version = index as i64 + 1;
+        logger::info(&format!("Applying migration {}: {}", version, name));
+        
+        // Take the write lock first so two workstations sharing the DB cannot both migrate
+        conn.execute_batch("BEGIN IMMEDIATE").map_err(|e| e.to_string())?;
+        if schema_version(conn)? >= version {
+            conn.execute_batch("COMMIT").map_err(|e| e.to_string())?;
+            continue;
+        }
+        
+        let result = migration(conn).and_then(|_| {
+            conn.execute_batch(&format!("PRAGMA user_version = {}", version))
+                .map_err(|e| e.to_string())
+        });
+        match result {
+            Ok(_) => conn.execute_batch("COMMIT").map_err(|e| e.to_string())?,
+            Err(e) => {
+                let _ = conn.execute_batch("ROLLBACK");
+                let err_msg = format!("Migration {} ({}) failed: {}", version, name, e);
+                logger::error(&err_msg);
+                return Err(err_msg);
+            }
+        }
+    }
+    
     Ok(())
 }
 
@@ -202,7 +477,6 @@ fn ensure_admin_user_exists(conn: &Connection) -> Result<(), String> {
         
         // Create admin user
         let password = "admin";
-        logger::info(&format!("Setting admin password to: {}", password));
         
         let hashed = hash(password, DEFAULT_COST)
             .map_err(|e| format!("Failed to hash password: {}", e))?;
@@ -253,7 +527,23 @@ pub fn get_projects(user_id: Option<i64>) -> Result<Vec<Project>, String> {
             (SELECT last_accessed FROM recent_projects WHERE user_id = ? AND project_id = p.id) as last_accessed");
     }
     
-    sql.push_str(" FROM projects p ORDER BY p.id DESC");
+    sql.push_str(" FROM projects p");
+    
+    // Restrict to projects the user is a member of (or unrestricted ones); admins see everything
+    let filter_uid = match user_id {
+        Some(uid) if access::is_admin(&conn, uid) => None,
+        Some(uid) => {
+            sql.push_str(" WHERE ");
+            sql.push_str(access::VISIBLE_PROJECT_FILTER);
+            Some(uid)
+        },
+        None => {
+            sql.push_str(" WHERE NOT EXISTS(SELECT 1 FROM project_members m WHERE m.project_id = p.id)");
+            None
+        }
+    };
+    
+    sql.push_str(" ORDER BY p.id DESC");
     
     let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
     
@@ -262,7 +552,7 @@ pub fn get_projects(user_id: Option<i64>) -> Result<Vec<Project>, String> {
         let id: i64 = row.get(0)?;
         let name: String = row.get(1)?;
         let client: Option<String> = row.get(2)?;
-        let path: String = row.get(3)?;
+        let path = paths::normalize_path(&row.get::<_, String>(3)?);
         let created_at: String = row.get(4)?;
         let updated_at: String = row.get(5)?;
         
@@ -283,10 +573,10 @@ pub fn get_projects(user_id: Option<i64>) -> Result<Vec<Project>, String> {
     };
     
     // Query with appropriate parameters
-    let projects: Vec<Project> = if let Some(uid) = user_id {
-        stmt.query_map(params![uid, uid], map_fn)
-    } else {
-        stmt.query_map([], map_fn)
+    let projects: Vec<Project> = match (user_id, filter_uid) {
+        (Some(uid), Some(fuid)) => stmt.query_map(params![uid, uid, fuid], map_fn),
+        (Some(uid), None) => stmt.query_map(params![uid, uid], map_fn),
+        _ => stmt.query_map([], map_fn),
     }.map_err(|e| e.to_string())?
       .map(|p| p.unwrap())
       .collect();
@@ -300,7 +590,7 @@ pub fn add_project(name: String, path: String, client: Option<String>) -> Result
     let now = Utc::now().to_rfc3339();
     conn.execute(
         "INSERT INTO projects (name, client, path, created_at, updated_at) VALUES (?, ?, ?, ?, ?)",
-        params![name, client, path, now, now],
+        params![name, client, paths::canonical_path(&path), now, now],
     ).map_err(|e| e.to_string())?;
     Ok(conn.last_insert_rowid())
 }
@@ -308,7 +598,7 @@ pub fn add_project(name: String, path: String, client: Option<String>) -> Result
 #[tauri::command]
 pub fn delete_project(projectId: i64) -> Result<bool, String> {
     // Simple log to confirm function is being called
-    println!("DELETE: Deleting project with ID {}", projectId);
+    logger::info(&format!("DELETE: Deleting project with ID {}", projectId));
     logger::info(&format!("DELETE: Starting deletion of project ID: {}", projectId));
     
     // Use get_connection instead of direct connection for consistency
@@ -325,36 +615,36 @@ pub fn delete_project(projectId: i64) -> Result<bool, String> {
     // First delete related records to avoid constraint violations
     let files_result = conn.execute("DELETE FROM project_files WHERE project_id = ?", params![projectId]);
     match files_result {
-        Ok(count) => println!("Deleted {} project files", count),
-        Err(e) => println!("Warning: couldn't delete project files: {}", e)
+        Ok(count) => logger::info(&format!("Deleted {} project files", count)),
+        Err(e) => logger::warn(&format!("Couldn't delete project files: {}", e))
     }
     
     // Delete recent projects references
     let recents_result = conn.execute("DELETE FROM recent_projects WHERE project_id = ?", params![projectId]);
     match recents_result {
-        Ok(count) => println!("Deleted {} recent project entries", count),
-        Err(e) => println!("Warning: couldn't delete recent projects: {}", e)
+        Ok(count) => logger::info(&format!("Deleted {} recent project entries", count)),
+        Err(e) => logger::warn(&format!("Couldn't delete recent projects: {}", e))
     }
     
     // Delete favorites
     let favorites_result = conn.execute("DELETE FROM user_favorites WHERE project_id = ?", params![projectId]);
     match favorites_result {
-        Ok(count) => println!("Deleted {} favorites", count),
-        Err(e) => println!("Warning: couldn't delete favorites: {}", e)
+        Ok(count) => logger::info(&format!("Deleted {} favorites", count)),
+        Err(e) => logger::warn(&format!("Couldn't delete favorites: {}", e))
     }
     
     // Now delete the actual project
-    println!("Attempting to delete project record...");
+    logger::info("Attempting to delete project record...");
     let delete_result = conn.execute("DELETE FROM projects WHERE id = ?", params![projectId]);
     
     match delete_result {
         Ok(count) => {
-            println!("SUCCESS: Deleted {} project(s) with ID {}", count, projectId);
+            logger::info(&format!("SUCCESS: Deleted {} project(s) with ID {}", count, projectId));
             Ok(count > 0)
         },
         Err(e) => {
             let err_msg = format!("Error deleting project: {}", e);
-            println!("{}", err_msg);
+            logger::error(&err_msg);
             Err(err_msg)
         }
     }
@@ -390,10 +680,7 @@ pub fn remove_project(project_id: i64) -> Result<bool, String> {
 // Ultra-simple, focused delete function that avoids any complexity
 #[tauri::command]
 pub fn emergency_delete_project(projectId: i64) -> Result<String, String> {
-    // Log to both terminal and logger
-    let msg = format!("EMERGENCY DELETE: Project ID {}", projectId);
-    println!("{}", msg);
-    logger::info(&msg);
+    logger::info(&format!("EMERGENCY DELETE: Project ID {}", projectId));
     
     // Open a simple direct connection
     let db_path = get_database_path();
@@ -405,19 +692,19 @@ pub fn emergency_delete_project(projectId: i64) -> Result<String, String> {
     // Skip foreign keys for emergency delete
     match conn.execute("PRAGMA foreign_keys = OFF;", []) {
         Ok(_) => {},
-        Err(e) => println!("Warning: Couldn't disable foreign keys: {}", e)
+        Err(e) => logger::warn(&format!("Couldn't disable foreign keys: {}", e))
     }
     
     // Delete directly using our camelCase parameter
     match conn.execute("DELETE FROM projects WHERE id = ?", params![projectId]) {
         Ok(rows) => {
             let result = format!("Successfully deleted {} project(s)", rows);
-            println!("{}", result);
+            logger::info(&result);
             Ok(result)
         },
         Err(e) => {
             let err = format!("Delete failed: {}", e);
-            println!("{}", err);
+            logger::error(&err);
             Err(err)
         }
     }
@@ -436,11 +723,36 @@ pub struct ProjectFile {
     pub shot_name: Option<String>,
     pub last_modified: String,
     pub created_at: String,
+    // Removed on disk since it was indexed, as seen by a project watcher
+    #[serde(default)]
+    pub missing: bool,
+}
+
+// Index a single file created by the launcher (rather than found by a scan); returns its id
+pub fn insert_project_file(conn: &Connection, file: &ProjectFile) -> Result<i64, String> {
+    conn.execute(
+        "INSERT INTO project_files (project_id, filename, version, file_type, path, relative_path, parent_folder, shot_name, last_modified, created_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        params![
+            file.project_id,
+            file.filename,
+            file.version,
+            file.file_type,
+            paths::canonical_path(&file.path),
+            file.relative_path,
+            file.parent_folder,
+            file.shot_name,
+            file.last_modified,
+            file.created_at
+        ],
+    ).map_err(|e| format!("Failed to index {}: {}", file.path, e))?;
+    Ok(conn.last_insert_rowid())
 }
 
 #[tauri::command]
 pub fn get_project_details(project_id: i64, user_id: Option<i64>) -> Result<Project, String> {
     let conn = get_connection().map_err(|e| e.to_string())?;
+    access::require_project_access(&conn, project_id, user_id)?;
     
     if let Some(uid) = user_id {
         // Update recent projects for this user
@@ -461,7 +773,7 @@ pub fn get_project_details(project_id: i64, user_id: Option<i64>) -> Result<Proj
                 id: row.get(0)?,
                 name: row.get(1)?,
                 client: row.get(2)?,
-                path: row.get(3)?,
+                path: paths::normalize_path(&row.get::<_, String>(3)?),
                 created_at: row.get(4)?,
                 updated_at: row.get(5)?,
                 is_favorite: Some(row.get::<_, i64>(6)? == 1),
@@ -478,7 +790,7 @@ pub fn get_project_details(project_id: i64, user_id: Option<i64>) -> Result<Proj
                 id: row.get(0)?,
                 name: row.get(1)?,
                 client: row.get(2)?,
-                path: row.get(3)?,
+                path: paths::normalize_path(&row.get::<_, String>(3)?),
                 created_at: row.get(4)?,
                 updated_at: row.get(5)?,
                 is_favorite: None,
@@ -490,33 +802,53 @@ pub fn get_project_details(project_id: i64, user_id: Option<i64>) -> Result<Proj
 }
 
 #[tauri::command]
-pub fn get_project_files(project_id: i64) -> Result<Vec<ProjectFile>, String> {
+pub fn get_project_files(project_id: i64, user_id: Option<i64>) -> Result<Vec<ProjectFile>, String> {
     let conn = get_connection().map_err(|e| e.to_string())?;
+    access::require_project_access(&conn, project_id, user_id)?;
     let files = conn.prepare(
-        "SELECT id, project_id, filename, version, file_type, path, relative_path, parent_folder, shot_name, last_modified, created_at FROM project_files WHERE project_id = ? ORDER BY filename ASC, version DESC"
+        &format!("SELECT {} FROM project_files WHERE project_id = ? AND missing = 0 ORDER BY filename ASC, version DESC", PROJECT_FILE_COLUMNS)
     ).map_err(|e| e.to_string())?
-      .query_map(params![project_id], |row| Ok(ProjectFile {
-            id: row.get(0)?,
-            project_id: row.get(1)?,
-            filename: row.get(2)?,
-            version: row.get(3)?,
-            file_type: row.get(4)?,
-            path: row.get(5)?,
-            relative_path: row.get(6)?,
-            parent_folder: row.get(7)?,
-            shot_name: row.get(8)?,
-            last_modified: row.get(9)?,
-            created_at: row.get(10)?,
-        })).map_err(|e| e.to_string())?
+      .query_map(params![project_id], map_project_file).map_err(|e| e.to_string())?
       .map(|f| f.unwrap())
       .collect();
     Ok(files)
 }
 
+// One indexed file as stored, for commands that take a file id rather than trusting the client
+pub fn get_project_file(conn: &Connection, file_id: i64) -> Result<ProjectFile, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM project_files WHERE id = ?", PROJECT_FILE_COLUMNS),
+        params![file_id],
+        map_project_file
+    ).optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("File {} not found", file_id))
+}
+
+const PROJECT_FILE_COLUMNS: &str =
+    "id, project_id, filename, version, file_type, path, relative_path, parent_folder, shot_name, last_modified, created_at, missing";
+
+fn map_project_file(row: &rusqlite::Row) -> rusqlite::Result<ProjectFile> {
+    Ok(ProjectFile {
+        id: row.get(0)?,
+        project_id: row.get(1)?,
+        filename: row.get(2)?,
+        version: row.get(3)?,
+        file_type: row.get(4)?,
+        path: paths::normalize_path(&row.get::<_, String>(5)?),
+        relative_path: row.get(6)?,
+        parent_folder: row.get(7)?,
+        shot_name: row.get(8)?,
+        last_modified: row.get(9)?,
+        created_at: row.get(10)?,
+        missing: row.get::<_, i64>(11)? != 0,
+    })
+}
+
+// Executable paths live in the application registry (see applications.rs); the old
+// settings columns are kept only so older clients sharing the DB keep working.
 #[derive(Serialize, Deserialize)]
 pub struct AppSettings {
-    pub nuke_executable_path: Option<String>,
-    pub ae_executable_path: Option<String>,
     pub default_scan_subdirs: Vec<String>,
     pub default_include_patterns: Vec<String>,
     pub default_exclude_patterns: Vec<String>,
@@ -526,15 +858,13 @@ pub struct AppSettings {
 pub fn get_settings() -> Result<AppSettings, String> {
     let conn = get_connection().map_err(|e| e.to_string())?;
     let row = conn.query_row(
-        "SELECT nuke_executable_path, ae_executable_path, default_scan_subdirs, default_include_patterns, default_exclude_patterns FROM settings WHERE id = 1", 
+        "SELECT default_scan_subdirs, default_include_patterns, default_exclude_patterns FROM settings WHERE id = 1", 
         [],
         |row| {
-            let scan: String = row.get(2)?;
-            let include: String = row.get(3)?;
-            let exclude: String = row.get(4)?;
+            let scan: String = row.get(0)?;
+            let include: String = row.get(1)?;
+            let exclude: String = row.get(2)?;
             Ok(AppSettings {
-                nuke_executable_path: row.get(0)?,
-                ae_executable_path: row.get(1)?,
                 default_scan_subdirs: scan.split(',').map(|s| s.trim().to_string()).collect(),
                 default_include_patterns: include.split(',').map(|s| s.trim().to_string()).collect(),
                 default_exclude_patterns: exclude.split(',').map(|s| s.trim().to_string()).collect(),
@@ -551,8 +881,8 @@ pub fn save_settings(settings: AppSettings) -> Result<bool, String> {
     let include = settings.default_include_patterns.join(",");
     let exclude = settings.default_exclude_patterns.join(",");
     conn.execute(
-        "UPDATE settings SET nuke_executable_path = ?, ae_executable_path = ?, default_scan_subdirs = ?, default_include_patterns = ?, default_exclude_patterns = ? WHERE id = 1", 
-        params![settings.nuke_executable_path, settings.ae_executable_path, scan, include, exclude],
+        "UPDATE settings SET default_scan_subdirs = ?, default_include_patterns = ?, default_exclude_patterns = ? WHERE id = 1", 
+        params![scan, include, exclude],
     ).map_err(|e| e.to_string())?;
     Ok(true)
 }
@@ -572,23 +902,25 @@ pub fn get_recent_projects(user_id: i64, limit: Option<i64>) -> Result<Vec<Proje
     
     let limit_value = limit.unwrap_or(5);
     
-    let mut stmt = conn.prepare(
+    let mut stmt = conn.prepare(&format!(
         "SELECT p.id, p.name, p.client, p.path, p.created_at, p.updated_at, 
          (SELECT EXISTS(SELECT 1 FROM user_favorites WHERE user_id = ? AND project_id = p.id)) as is_favorite,
          r.last_accessed
          FROM recent_projects r
          JOIN projects p ON r.project_id = p.id
-         WHERE r.user_id = ?
+         WHERE r.user_id = ? AND (? OR {})
          ORDER BY r.last_accessed DESC
-         LIMIT ?"
-    ).map_err(|e| e.to_string())?;
+         LIMIT ?",
+        access::VISIBLE_PROJECT_FILTER
+    )).map_err(|e| e.to_string())?;
     
-    let projects = stmt.query_map(params![user_id, user_id, limit_value], |row| {
+    let is_admin = access::is_admin(&conn, user_id);
+    let projects = stmt.query_map(params![user_id, user_id, is_admin, user_id, limit_value], |row| {
         Ok(Project {
             id: row.get(0)?,
             name: row.get(1)?,
             client: row.get(2)?,
-            path: row.get(3)?,
+            path: paths::normalize_path(&row.get::<_, String>(3)?),
             created_at: row.get(4)?,
             updated_at: row.get(5)?,
             is_favorite: Some(row.get::<_, i64>(6)? == 1),
@@ -605,21 +937,23 @@ pub fn get_recent_projects(user_id: i64, limit: Option<i64>) -> Result<Vec<Proje
 pub fn get_favorite_projects(user_id: i64) -> Result<Vec<Project>, String> {
     let conn = get_connection().map_err(|e| e.to_string())?;
     
-    let mut stmt = conn.prepare(
+    let mut stmt = conn.prepare(&format!(
         "SELECT p.id, p.name, p.client, p.path, p.created_at, p.updated_at,
          (SELECT last_accessed FROM recent_projects WHERE user_id = ? AND project_id = p.id) as last_accessed
          FROM user_favorites f
          JOIN projects p ON f.project_id = p.id
-         WHERE f.user_id = ?
-         ORDER BY f.created_at DESC"
-    ).map_err(|e| e.to_string())?;
+         WHERE f.user_id = ? AND (? OR {})
+         ORDER BY f.created_at DESC",
+        access::VISIBLE_PROJECT_FILTER
+    )).map_err(|e| e.to_string())?;
     
-    let projects = stmt.query_map(params![user_id, user_id], |row| {
+    let is_admin = access::is_admin(&conn, user_id);
+    let projects = stmt.query_map(params![user_id, user_id, is_admin, user_id], |row| {
         Ok(Project {
             id: row.get(0)?,
             name: row.get(1)?,
             client: row.get(2)?,
-            path: row.get(3)?,
+            path: paths::normalize_path(&row.get::<_, String>(3)?),
             created_at: row.get(4)?,
             updated_at: row.get(5)?,
             is_favorite: Some(true),
@@ -651,11 +985,7 @@ pub fn toggle_favorite_project(user_id: i64, project_id: i64) -> Result<bool, St
         ).map_err(|e| e.to_string())?;
         
         // Log activity
-        let now = Utc::now().to_rfc3339();
-        conn.execute(
-            "INSERT INTO user_activity (user_id, activity_type, project_id, details, timestamp) VALUES (?, ?, ?, ?, ?)",
-            params![user_id, "remove_favorite", project_id, "Removed project from favorites", now],
-        ).map_err(|e| e.to_string())?;
+        activity::insert_activity(&conn, user_id, ActivityType::RemoveFavorite, Some(project_id), None, Some("Removed project from favorites"))?;
         
         Ok(false) // Return new state (not favorited)
     } else {
@@ -667,10 +997,7 @@ pub fn toggle_favorite_project(user_id: i64, project_id: i64) -> Result<bool, St
         ).map_err(|e| e.to_string())?;
         
         // Log activity
-        conn.execute(
-            "INSERT INTO user_activity (user_id, activity_type, project_id, details, timestamp) VALUES (?, ?, ?, ?, ?)",
-            params![user_id, "add_favorite", project_id, "Added project to favorites", now],
-        ).map_err(|e| e.to_string())?;
+        activity::insert_activity(&conn, user_id, ActivityType::AddFavorite, Some(project_id), None, Some("Added project to favorites"))?;
         
         Ok(true) // Return new state (favorited)
     }
@@ -0,0 +1,397 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime};
+use log::Level;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use rusqlite::params;
+use serde::{Serialize, Deserialize};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+use crate::access;
+use crate::config;
+use crate::db;
+use crate::logger;
+use crate::paths;
+use crate::redact;
+use crate::watcher;
+
+// Lines returned by tail_logs when no limit is given
+const DEFAULT_TAIL: usize = 500;
+
+// Logs modified within this many days go into a diagnostics bundle
+const BUNDLE_LOG_DAYS: u64 = 7;
+
+// "[2024-05-01 12:00:00.000] [INFO] [watcher] message"; lines written before modules were
+// logged have no module
+static TEXT_LINE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\[(\d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2}(?:\.\d+)?)\] \[([A-Z]+)\](?: \[([^\]]*)\])? (.*)$").unwrap()
+});
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LogEntry {
+    pub timestamp: String,
+    pub level: String,
+    pub module: Option<String>,
+    pub message: String,
+    // Log file the entry was read from
+    pub file: String,
+}
+
+// Filters for tail_logs; every field is optional
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct LogFilter {
+    // Least severe level to include, e.g. "warn" for warnings and errors
+    pub level: Option<String>,
+    pub module: Option<String>,
+    // RFC 3339, "YYYY-MM-DD HH:MM[:SS]" or "YYYY-MM-DD", in local time unless an offset is given
+    pub since: Option<String>,
+    pub until: Option<String>,
+    // Case-insensitive substring of the message
+    pub text: Option<String>,
+    pub limit: Option<usize>,
+}
+
+fn parse_time(value: &str) -> Option<NaiveDateTime> {
+    let value = value.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(time.with_timezone(&Local).naive_local());
+    }
+    for format in ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M"] {
+        if let Ok(time) = NaiveDateTime::parse_from_str(value, format) {
+            return Some(time);
+        }
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").ok().and_then(|date| date.and_hms_opt(0, 0, 0))
+}
+
+fn parse_json_line(line: &str, file: &str) -> Option<LogEntry> {
+    let value: serde_json::Value = serde_json::from_str(line).ok()?;
+    Some(LogEntry {
+        timestamp: value.get("timestamp")?.as_str()?.to_string(),
+        level: value.get("level")?.as_str()?.to_string(),
+        module: value.get("module").and_then(|m| m.as_str()).map(|m| m.to_string()),
+        message: value.get("message").and_then(|m| m.as_str()).unwrap_or_default().to_string(),
+        file: file.to_string(),
+    })
+}
+
+// Entries of one log file, oldest first. Lines that do not start an entry are continuations
+// of a multi-line message.
+fn read_entries(path: &Path) -> Vec<LogEntry> {
+    let contents = match fs::read(path) {
+        Ok(bytes) => String::from_utf8_lossy(&bytes).to_string(),
+        Err(e) => {
+            logger::warn(&format!("Failed to read log {}: {}", path.display(), e));
+            return Vec::new();
+        }
+    };
+    let file = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let json = path.extension().map(|ext| ext == "jsonl").unwrap_or(false);
+
+    let mut entries: Vec<LogEntry> = Vec::new();
+    for line in contents.lines() {
+        if json {
+            entries.extend(parse_json_line(line, &file));
+        } else if let Some(caps) = TEXT_LINE.captures(line) {
+            entries.push(LogEntry {
+                timestamp: caps[1].to_string(),
+                level: caps[2].to_string(),
+                module: caps.get(3).map(|m| m.as_str().to_string()),
+                message: caps[4].to_string(),
+                file: file.clone(),
+            });
+        } else if let Some(last) = entries.last_mut() {
+            last.message.push('\n');
+            last.message.push_str(line);
+        }
+    }
+    entries
+}
+
+struct CompiledFilter {
+    level: Option<Level>,
+    module: Option<String>,
+    since: Option<NaiveDateTime>,
+    until: Option<NaiveDateTime>,
+    text: Option<String>,
+}
+
+impl CompiledFilter {
+    fn new(filter: &LogFilter) -> Result<Self, String> {
+        let time = |value: &Option<String>| -> Result<Option<NaiveDateTime>, String> {
+            match value.as_deref().filter(|v| !v.trim().is_empty()) {
+                Some(v) => parse_time(v).map(Some).ok_or_else(|| format!("Unrecognised time \"{}\"", v)),
+                None => Ok(None),
+            }
+        };
+        Ok(CompiledFilter {
+            level: match filter.level.as_deref().filter(|l| !l.is_empty()) {
+                Some(level) => Some(level.parse().map_err(|_| format!("Unknown log level \"{}\"", level))?),
+                None => None,
+            },
+            module: filter.module.clone().filter(|m| !m.is_empty()),
+            since: time(&filter.since)?,
+            until: time(&filter.until)?,
+            text: filter.text.as_ref().filter(|t| !t.is_empty()).map(|t| t.to_lowercase()),
+        })
+    }
+
+    fn matches(&self, entry: &LogEntry) -> bool {
+        if let Some(min) = self.level {
+            match entry.level.parse::<Level>() {
+                Ok(level) if level <= min => (),
+                _ => return false,
+            }
+        }
+        if let Some(module) = &self.module {
+            if entry.module.as_deref() != Some(module.as_str()) {
+                return false;
+            }
+        }
+        if self.since.is_some() || self.until.is_some() {
+            let time = match parse_time(&entry.timestamp) {
+                Some(time) => time,
+                None => return false,
+            };
+            if self.since.map(|since| time < since).unwrap_or(false) || self.until.map(|until| time > until).unwrap_or(false) {
+                return false;
+            }
+        }
+        if let Some(text) = &self.text {
+            if !entry.message.to_lowercase().contains(text) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+// The logs record every user's activity, so only admins read them or export them
+fn require_admin(acting_user_id: i64) -> Result<(), String> {
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    if access::is_admin(&conn, acting_user_id) {
+        Ok(())
+    } else {
+        Err("Only admins can read the application logs".to_string())
+    }
+}
+
+// The newest log entries matching the filter, oldest first. Rotated files are only read
+// when the current one has too few matches.
+#[tauri::command]
+pub fn tail_logs(filter: Option<LogFilter>, acting_user_id: i64) -> Result<Vec<LogEntry>, String> {
+    require_admin(acting_user_id)?;
+    let filter = filter.unwrap_or_default();
+    let limit = filter.limit.unwrap_or(DEFAULT_TAIL).max(1);
+    let compiled = CompiledFilter::new(&filter)?;
+    logger::flush();
+
+    let mut matched: Vec<LogEntry> = Vec::new();
+    for path in logger::log_files() {
+        let mut entries: Vec<LogEntry> = read_entries(&path).into_iter().filter(|e| compiled.matches(e)).collect();
+        entries.append(&mut matched);
+        matched = entries;
+        if matched.len() >= limit {
+            break;
+        }
+    }
+
+    let skip = matched.len().saturating_sub(limit);
+    Ok(matched.split_off(skip))
+}
+
+#[derive(Serialize)]
+struct DatabaseReport {
+    mode: String,
+    path: String,
+    accessible: bool,
+    schema_version: Option<i64>,
+    latest_schema_version: i64,
+    error: Option<String>,
+}
+
+fn database_report() -> DatabaseReport {
+    let cfg = config::get_config();
+    let path = paths::get_database_path();
+    let mut report = DatabaseReport {
+        mode: format!("{:?}", cfg.database.mode).to_lowercase(),
+        path: redact::redact(&path.to_string_lossy(), &[]).into_owned(),
+        accessible: path.exists(),
+        schema_version: None,
+        latest_schema_version: db::latest_schema_version(),
+        error: None,
+    };
+    match db::get_connection().map_err(|e| e.to_string()).and_then(|conn| db::schema_version(&conn)) {
+        Ok(version) => report.schema_version = Some(version),
+        Err(e) => report.error = Some(e),
+    }
+    report
+}
+
+#[derive(Serialize)]
+struct MappingReport {
+    name: String,
+    // This machine's root for the mapping, and whether it can be reached
+    local_root: Option<String>,
+    reachable: bool,
+}
+
+#[derive(Serialize)]
+struct PathReport {
+    mappings: Vec<MappingReport>,
+    // How every project's stored path resolves on this machine
+    projects: Vec<paths::PathResolution>,
+}
+
+fn path_report() -> PathReport {
+    let os = paths::get_os_type();
+    let mappings = paths::mapping_rules().into_iter().map(|rule| {
+        let local_root = rule.root_for(&os).map(|root| root.to_string());
+        MappingReport {
+            reachable: local_root.as_deref().map(paths::check_path_access).unwrap_or(false),
+            name: rule.name,
+            local_root,
+        }
+    }).collect();
+
+    let projects = db::get_connection()
+        .and_then(|conn| {
+            let mut stmt = conn.prepare("SELECT path FROM projects ORDER BY id")?;
+            let rows = stmt.query_map(params![], |row| row.get::<_, String>(0))?;
+            rows.collect::<rusqlite::Result<Vec<String>>>()
+        })
+        .unwrap_or_default()
+        .iter()
+        .map(|path| paths::resolve(path))
+        .collect();
+
+    PathReport { mappings, projects }
+}
+
+#[derive(Serialize)]
+struct SystemReport {
+    app_version: &'static str,
+    os: &'static str,
+    os_version: Option<String>,
+    arch: &'static str,
+    machine: String,
+    generated_at: String,
+    log_dir: String,
+}
+
+// Human-readable OS release, e.g. "macOS 14.4" or "Ubuntu 22.04.4 LTS"
+fn os_version() -> Option<String> {
+    #[cfg(target_os = "linux")]
+    {
+        let release = fs::read_to_string("/etc/os-release").ok()?;
+        release.lines()
+            .find_map(|line| line.strip_prefix("PRETTY_NAME="))
+            .map(|name| name.trim_matches('"').to_string())
+    }
+    #[cfg(target_os = "macos")]
+    {
+        let output = std::process::Command::new("sw_vers").arg("-productVersion").output().ok()?;
+        Some(format!("macOS {}", String::from_utf8_lossy(&output.stdout).trim()))
+    }
+    #[cfg(target_os = "windows")]
+    {
+        let output = std::process::Command::new("cmd").args(["/C", "ver"]).output().ok()?;
+        Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+    {
+        None
+    }
+}
+
+fn system_report() -> SystemReport {
+    SystemReport {
+        app_version: env!("CARGO_PKG_VERSION"),
+        os: std::env::consts::OS,
+        os_version: os_version(),
+        arch: std::env::consts::ARCH,
+        machine: paths::get_machine_name(),
+        generated_at: Local::now().to_rfc3339(),
+        log_dir: logger::log_dir().to_string_lossy().to_string(),
+    }
+}
+
+fn recent_logs() -> Vec<PathBuf> {
+    let cutoff = SystemTime::now() - Duration::from_secs(BUNDLE_LOG_DAYS * 24 * 60 * 60);
+    logger::log_files()
+        .into_iter()
+        .enumerate()
+        // The current log always goes in, however old
+        .filter(|(i, path)| *i == 0 || fs::metadata(path).and_then(|m| m.modified()).map(|m| m >= cutoff).unwrap_or(false))
+        .map(|(_, path)| path)
+        .collect()
+}
+
+fn add_json<T: Serialize>(zip: &mut ZipWriter<File>, name: &str, value: &T) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+    add_bytes(zip, name, json.as_bytes())
+}
+
+fn add_bytes(zip: &mut ZipWriter<File>, name: &str, bytes: &[u8]) -> Result<(), String> {
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    zip.start_file(name, options).map_err(|e| format!("Failed to add {}: {}", name, e))?;
+    zip.write_all(bytes).map_err(|e| format!("Failed to add {}: {}", name, e))
+}
+
+fn default_bundle_path() -> PathBuf {
+    let log_dir = logger::log_dir();
+    let dir = log_dir.parent().map(|parent| parent.join("diagnostics")).unwrap_or_else(|| PathBuf::from("diagnostics"));
+    dir.join(format!("vfx-diagnostics-{}-{}.zip", paths::get_machine_name(), Local::now().format("%Y%m%d-%H%M%S")))
+}
+
+// Zip up what support needs from this workstation: recent logs, the effective configuration
+// with secrets masked, database and watcher state, path mapping results and OS details.
+// Returns the path of the bundle, which must not exist yet.
+#[tauri::command]
+pub fn export_diagnostics(output_path: Option<String>, acting_user_id: i64) -> Result<String, String> {
+    require_admin(acting_user_id)?;
+    let path = output_path.filter(|p| !p.trim().is_empty()).map(PathBuf::from).unwrap_or_else(default_bundle_path);
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    logger::info(&format!("Writing diagnostics bundle to {}", path.display()));
+    logger::flush();
+
+    let file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let mut zip = ZipWriter::new(file);
+
+    add_json(&mut zip, "system.json", &system_report())?;
+
+    let config = config::redacted_config()?;
+    let config_text = toml::to_string_pretty(&config).map_err(|e| e.to_string())?;
+    add_bytes(&mut zip, "config/effective.toml", config_text.as_bytes())?;
+    let mut sources = config::get_config_sources()?;
+    for source in sources.iter_mut() {
+        redact::redact_toml(&source.key, &mut source.value);
+    }
+    add_json(&mut zip, "config/sources.json", &sources)?;
+    add_json(&mut zip, "config/errors.json", &config::get_config_errors()?)?;
+
+    add_json(&mut zip, "database.json", &database_report())?;
+    add_json(&mut zip, "watchers.json", &watcher::get_watching_projects()?)?;
+    add_json(&mut zip, "paths.json", &path_report())?;
+
+    // Logs are already redacted as they are written
+    for log in recent_logs() {
+        let name = log.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        match fs::read(&log) {
+            Ok(bytes) => add_bytes(&mut zip, &format!("logs/{}", name), &bytes)?,
+            Err(e) => logger::warn(&format!("Leaving {} out of the diagnostics bundle: {}", log.display(), e)),
+        }
+    }
+
+    zip.finish().map_err(|e| format!("Failed to finish {}: {}", path.display(), e))?;
+    Ok(path.to_string_lossy().to_string())
+}
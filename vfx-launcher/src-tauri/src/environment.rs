@@ -0,0 +1,489 @@
+use std::collections::HashMap;
+use std::env;
+use serde::{Serialize, Deserialize};
+use rusqlite::{params, Connection, OptionalExtension};
+use chrono::Utc;
+use regex::{Captures, Regex};
+use crate::activity::{self, ActivityType};
+use crate::access;
+use crate::applications::{self, Application};
+use crate::db;
+use crate::logger;
+use crate::paths::{self, OsType};
+use crate::redact;
+
+// One variable of an environment profile. Profiles are applied global first, then project,
+// then application, so the more specific scope wins for `set`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct EnvironmentVariable {
+    pub id: i64, // 0 for a new entry
+    // "global", "project" or "application"
+    pub scope: String,
+    // Project id for project scope; application name, or "name version", for application scope
+    pub scope_key: Option<String>,
+    pub name: String,
+    pub value: String,
+    // "set", "prepend" or "append"; the last two treat the variable as a path list
+    pub operation: String,
+}
+
+// A variable as it will be passed to the launched application, with where it came from
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ResolvedVariable {
+    pub name: String,
+    pub value: String,
+    pub source: String,
+}
+
+// Per-shot settings, exposed to launches as FIRST_FRAME, LAST_FRAME, FORMAT and PLATE_PATH
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Shot {
+    pub project_id: i64,
+    pub name: String,
+    pub first_frame: Option<i64>,
+    pub last_frame: Option<i64>,
+    // Nuke format name or size, e.g. "UHD_4K" or "3840 2160 0 0 3840 2160 1"
+    pub format: Option<String>,
+    pub plate_path: Option<String>,
+}
+
+// Context known about the file being launched
+struct LaunchContext {
+    project_id: i64,
+    project_name: String,
+    project_path: String,
+    shot_name: Option<String>,
+    parent_folder: Option<String>,
+    file_path: String,
+    version: String,
+}
+
+fn validate(variable: &EnvironmentVariable) -> Result<(), String> {
+    if !matches!(variable.scope.as_str(), "global" | "project" | "application") {
+        return Err(format!("Unknown environment scope: {}", variable.scope));
+    }
+    if !matches!(variable.operation.as_str(), "set" | "prepend" | "append") {
+        return Err(format!("Unknown environment operation: {}", variable.operation));
+    }
+    let name_regex = Regex::new(r"^[A-Za-z_][A-Za-z0-9_]*$").unwrap();
+    if !name_regex.is_match(&variable.name) {
+        return Err(format!("Invalid environment variable name: {}", variable.name));
+    }
+    if variable.scope != "global" && variable.scope_key.as_deref().unwrap_or("").trim().is_empty() {
+        return Err(format!("A {} environment variable needs a {}", variable.scope, variable.scope));
+    }
+    Ok(())
+}
+
+fn path_list_separator() -> &'static str {
+    if paths::get_os_type() == OsType::Windows { ";" } else { ":" }
+}
+
+// Expand `$VAR` and `${VAR}` against the environment built so far; unknown variables expand to nothing
+fn expand(value: &str, current: &HashMap<String, String>) -> String {
+    let var_regex = Regex::new(r"\$\{([A-Za-z_][A-Za-z0-9_]*)\}|\$([A-Za-z_][A-Za-z0-9_]*)").unwrap();
+    var_regex.replace_all(value, |caps: &Captures| {
+        let name = caps.get(1).or_else(|| caps.get(2)).map(|m| m.as_str()).unwrap_or("");
+        current.get(name).cloned()
+            .or_else(|| env::var(name).ok())
+            .unwrap_or_default()
+    }).to_string()
+}
+
+fn map_variable(row: &rusqlite::Row) -> rusqlite::Result<EnvironmentVariable> {
+    let scope_key: String = row.get(2)?;
+    Ok(EnvironmentVariable {
+        id: row.get(0)?,
+        scope: row.get(1)?,
+        scope_key: Some(scope_key).filter(|k| !k.is_empty()),
+        name: row.get(3)?,
+        value: row.get(4)?,
+        operation: row.get(5)?,
+    })
+}
+
+fn load_scope(conn: &Connection, scope: &str, scope_key: &str) -> Result<Vec<EnvironmentVariable>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT id, scope, scope_key, name, value, operation FROM environment_variables
+         WHERE scope = ? AND scope_key = ? ORDER BY id ASC"
+    ).map_err(|e| e.to_string())?;
+    let variables = stmt.query_map(params![scope, scope_key], map_variable)
+        .map_err(|e| e.to_string())?
+        .collect::<rusqlite::Result<Vec<EnvironmentVariable>>>()
+        .map_err(|e| e.to_string())?;
+    Ok(variables)
+}
+
+fn load_context(conn: &Connection, file_id: i64) -> Result<Option<LaunchContext>, String> {
+    conn.query_row(
+        "SELECT p.id, p.name, p.path, f.shot_name, f.parent_folder, f.path, f.version
+         FROM project_files f
+         JOIN projects p ON f.project_id = p.id
+         WHERE f.id = ?",
+        params![file_id],
+        |row| Ok(LaunchContext {
+            project_id: row.get(0)?,
+            project_name: row.get(1)?,
+            project_path: row.get(2)?,
+            shot_name: row.get(3)?,
+            parent_folder: row.get(4)?,
+            file_path: row.get(5)?,
+            version: row.get(6)?,
+        })
+    ).optional().map_err(|e| e.to_string())
+}
+
+// Shots are named <SEQ>_<number>, e.g. ABC_0010
+pub fn sequence_of(shot: &str) -> Option<&str> {
+    shot.rsplit_once('_').map(|(seq, _)| seq)
+}
+
+fn map_shot(row: &rusqlite::Row) -> rusqlite::Result<Shot> {
+    Ok(Shot {
+        project_id: row.get(0)?,
+        name: row.get(1)?,
+        first_frame: row.get(2)?,
+        last_frame: row.get(3)?,
+        format: row.get(4)?,
+        plate_path: row.get::<_, Option<String>>(5)?.map(|path| paths::normalize_path(&path)),
+    })
+}
+
+pub fn get_shot(conn: &Connection, project_id: i64, name: &str) -> Result<Option<Shot>, String> {
+    conn.query_row(
+        "SELECT project_id, name, first_frame, last_frame, format, plate_path FROM shots WHERE project_id = ? AND name = ?",
+        params![project_id, name],
+        map_shot
+    ).optional().map_err(|e| e.to_string())
+}
+
+// The settings of a shot that are set, as context variables
+pub fn shot_variables(shot: &Shot) -> Vec<(String, String)> {
+    let mut vars = Vec::new();
+    if let Some(first) = shot.first_frame {
+        vars.push(("FIRST_FRAME".to_string(), first.to_string()));
+    }
+    if let Some(last) = shot.last_frame {
+        vars.push(("LAST_FRAME".to_string(), last.to_string()));
+    }
+    if let Some(format) = shot.format.as_ref().filter(|f| !f.is_empty()) {
+        vars.push(("FORMAT".to_string(), format.clone()));
+    }
+    if let Some(plate) = shot.plate_path.as_ref().filter(|p| !p.is_empty()) {
+        vars.push(("PLATE_PATH".to_string(), paths::normalize_path(plate)));
+    }
+    vars
+}
+
+// Databases created before shot formats and plates lack those columns
+pub fn add_shot_detail_columns(conn: &Connection) -> Result<(), String> {
+    for column in ["format", "plate_path"] {
+        let has_column: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM pragma_table_info('shots') WHERE name = ?)",
+            params![column],
+            |row| row.get(0)
+        ).map_err(|e| e.to_string())?;
+        if !has_column {
+            conn.execute_batch(&format!("ALTER TABLE shots ADD COLUMN {} TEXT", column))
+                .map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(())
+}
+
+// Shot context variables: PROJECT, SEQ, SHOT, TASK and the shot settings
+fn context_variables(conn: &Connection, context: &LaunchContext) -> Result<Vec<(String, String)>, String> {
+    let mut vars = vec![
+        ("PROJECT".to_string(), context.project_name.clone()),
+        ("PROJECT_PATH".to_string(), paths::normalize_path(&context.project_path)),
+        ("FILE".to_string(), paths::normalize_path(&context.file_path)),
+        ("VERSION".to_string(), context.version.clone()),
+    ];
+
+    if let Some(shot) = &context.shot_name {
+        vars.push(("SHOT".to_string(), shot.clone()));
+        if let Some(seq) = sequence_of(shot) {
+            vars.push(("SEQ".to_string(), seq.to_string()));
+        }
+        if let Some(settings) = get_shot(conn, context.project_id, shot)? {
+            vars.extend(shot_variables(&settings));
+        }
+    }
+
+    // The task is the folder the script lives in, e.g. shots/ABC_0010/comp -> comp
+    let task = context.parent_folder.as_deref()
+        .and_then(|folder| folder.rsplit(['/', '\\']).next())
+        .filter(|task| !task.is_empty() && Some(*task) != context.shot_name.as_deref());
+    if let Some(task) = task {
+        vars.push(("TASK".to_string(), task.to_string()));
+    }
+
+    Ok(vars)
+}
+
+fn apply(resolved: &mut Vec<ResolvedVariable>, name: &str, value: String, source: &str) {
+    match resolved.iter_mut().find(|v| v.name == name) {
+        Some(existing) => {
+            existing.value = value;
+            existing.source = source.to_string();
+        },
+        None => resolved.push(ResolvedVariable { name: name.to_string(), value, source: source.to_string() }),
+    }
+}
+
+// Build the variables to add to the inherited environment when launching a file.
+// Context variables come first so profiles can refer to them, e.g. OCIO=/shows/$PROJECT/config.ocio
+pub fn resolve_launch_environment(
+    conn: &Connection,
+    file_id: Option<i64>,
+    application: Option<&Application>
+) -> Result<Vec<ResolvedVariable>, String> {
+    let mut resolved: Vec<ResolvedVariable> = Vec::new();
+
+    let context = match file_id {
+        Some(id) => load_context(conn, id)?,
+        None => None,
+    };
+    if let Some(context) = &context {
+        for (name, value) in context_variables(conn, context)? {
+            apply(&mut resolved, &name, value, "context");
+        }
+    }
+
+    let mut layers = vec![("global".to_string(), String::new())];
+    if let Some(context) = &context {
+        layers.push(("project".to_string(), context.project_id.to_string()));
+    }
+    if let Some(app) = application {
+        layers.push(("application".to_string(), app.name.clone()));
+        layers.push(("application".to_string(), app.label()));
+    }
+
+    for (scope, scope_key) in layers {
+        let source = if scope_key.is_empty() { scope.clone() } else { format!("{} {}", scope, scope_key) };
+        for variable in load_scope(conn, &scope, &scope_key)? {
+            let lookup: HashMap<String, String> = resolved.iter().map(|v| (v.name.clone(), v.value.clone())).collect();
+            let value = expand(&variable.value, &lookup);
+            let existing = lookup.get(&variable.name).cloned()
+                .or_else(|| env::var(&variable.name).ok())
+                .filter(|v| !v.is_empty());
+
+            let value = match (variable.operation.as_str(), existing) {
+                ("prepend", Some(existing)) => format!("{}{}{}", value, path_list_separator(), existing),
+                ("append", Some(existing)) => format!("{}{}{}", existing, path_list_separator(), value),
+                _ => value,
+            };
+            apply(&mut resolved, &variable.name, value, &source);
+        }
+    }
+
+    Ok(resolved)
+}
+
+// A profile for its editor: project owners see their project's, admins every scope. Values
+// can hold license servers or tokens, so nobody else lists them.
+#[tauri::command]
+pub fn get_environment_variables(scope: Option<String>, scope_key: Option<String>, acting_user_id: i64) -> Result<Vec<EnvironmentVariable>, String> {
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    match scope {
+        Some(scope) => {
+            let scope_key = scope_key.unwrap_or_default();
+            require_scope_permission(&conn, &scope, &scope_key, acting_user_id)?;
+            load_scope(&conn, &scope, &scope_key)
+        },
+        None if !access::is_admin(&conn, acting_user_id) => {
+            Err("Only admins can list every environment profile".to_string())
+        },
+        None => {
+            let mut stmt = conn.prepare(
+                "SELECT id, scope, scope_key, name, value, operation FROM environment_variables
+                 ORDER BY scope ASC, scope_key ASC, id ASC"
+            ).map_err(|e| e.to_string())?;
+            let variables = stmt.query_map([], map_variable)
+                .map_err(|e| e.to_string())?
+                .collect::<rusqlite::Result<Vec<EnvironmentVariable>>>()
+                .map_err(|e| e.to_string())?;
+            Ok(variables)
+        }
+    }
+}
+
+// A value as it may appear in logs, activity and the launch preview; variables with a secret
+// name such as LICENSE_TOKEN are masked
+fn display_value(name: &str, value: &str) -> String {
+    if redact::is_secret_field(name) {
+        redact::REDACTED.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+fn load_variable(conn: &Connection, id: i64) -> Result<Option<EnvironmentVariable>, String> {
+    conn.query_row(
+        "SELECT id, scope, scope_key, name, value, operation FROM environment_variables WHERE id = ?",
+        params![id],
+        map_variable
+    ).optional().map_err(|e| e.to_string())
+}
+
+// Project owners manage their project's profile; the global and application profiles affect
+// every launch, so they are admin-only. Returns the project id for project scope.
+fn require_scope_permission(conn: &Connection, scope: &str, scope_key: &str, acting_user_id: i64) -> Result<Option<i64>, String> {
+    if scope == "project" {
+        let id = scope_key.trim().parse::<i64>().map_err(|_| format!("Invalid project id: {}", scope_key))?;
+        access::require_project_owner(conn, id, acting_user_id)?;
+        Ok(Some(id))
+    } else if access::is_admin(conn, acting_user_id) {
+        Ok(None)
+    } else {
+        Err("Only admins can change global and application environments".to_string())
+    }
+}
+
+// Add (id 0) or update a variable. Updates need permission for the scope the variable is
+// moving out of as well as the one it is moving into.
+#[tauri::command]
+pub fn save_environment_variable(variable: EnvironmentVariable, acting_user_id: i64) -> Result<i64, String> {
+    validate(&variable)?;
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+
+    if variable.id != 0 {
+        let existing = load_variable(&conn, variable.id)?
+            .ok_or_else(|| format!("Environment variable {} not found", variable.id))?;
+        require_scope_permission(&conn, &existing.scope, existing.scope_key.as_deref().unwrap_or(""), acting_user_id)?;
+    }
+
+    let scope_key = if variable.scope == "global" {
+        String::new()
+    } else {
+        variable.scope_key.clone().unwrap_or_default().trim().to_string()
+    };
+    let project_id = require_scope_permission(&conn, &variable.scope, &scope_key, acting_user_id)?;
+
+    let now = Utc::now().to_rfc3339();
+    let id = if variable.id == 0 {
+        conn.execute(
+            "INSERT INTO environment_variables (scope, scope_key, name, value, operation, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?)",
+            params![variable.scope, scope_key, variable.name, variable.value, variable.operation, now, now],
+        ).map_err(|e| e.to_string())?;
+        conn.last_insert_rowid()
+    } else {
+        conn.execute(
+            "UPDATE environment_variables SET scope = ?, scope_key = ?, name = ?, value = ?, operation = ?, updated_at = ? WHERE id = ?",
+            params![variable.scope, scope_key, variable.name, variable.value, variable.operation, now, variable.id],
+        ).map_err(|e| e.to_string())?;
+        variable.id
+    };
+
+    let details = format!("{} {}={} ({})", variable.operation, variable.name, display_value(&variable.name, &variable.value), variable.scope);
+    activity::record_activity(&conn, acting_user_id, ActivityType::SaveEnvironmentVariable, project_id, None, &details);
+    logger::info(&format!("Saved environment variable: {}", details));
+    Ok(id)
+}
+
+#[tauri::command]
+pub fn delete_environment_variable(id: i64, acting_user_id: i64) -> Result<bool, String> {
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    let variable = match load_variable(&conn, id)? {
+        Some(variable) => variable,
+        None => return Ok(false),
+    };
+    let project_id = require_scope_permission(&conn, &variable.scope, variable.scope_key.as_deref().unwrap_or(""), acting_user_id)?;
+
+    conn.execute("DELETE FROM environment_variables WHERE id = ?", params![id])
+        .map_err(|e| e.to_string())?;
+    activity::record_activity(&conn, acting_user_id, ActivityType::DeleteEnvironmentVariable, project_id, None, &format!("Removed {} ({})", variable.name, variable.scope));
+    Ok(true)
+}
+
+// The variables `open_file` would add for this file, for debugging launch problems
+#[tauri::command]
+pub fn get_launch_environment(
+    file_id: i64,
+    app_version: Option<String>,
+    user_id: Option<i64>
+) -> Result<Vec<ResolvedVariable>, String> {
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    let (project_id, shot_name, file_type): (i64, Option<String>, String) = conn.query_row(
+        "SELECT project_id, shot_name, file_type FROM project_files WHERE id = ?",
+        params![file_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+    ).optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("File {} not found", file_id))?;
+    access::require_project_access(&conn, project_id, user_id)?;
+
+    // Show the profile even when no application is registered for the file type
+    let application = applications::resolve_launch_application(&conn, &file_type, Some(project_id), shot_name.as_deref(), app_version.as_deref()).ok();
+    let mut variables = resolve_launch_environment(&conn, Some(file_id), application.as_ref())?;
+    if !user_id.map(|uid| access::is_admin(&conn, uid)).unwrap_or(false) {
+        for variable in &mut variables {
+            variable.value = display_value(&variable.name, &variable.value);
+        }
+    }
+    Ok(variables)
+}
+
+#[tauri::command]
+pub fn get_shots(project_id: i64, user_id: Option<i64>) -> Result<Vec<Shot>, String> {
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    access::require_project_access(&conn, project_id, user_id)?;
+
+    let mut stmt = conn.prepare(
+        "SELECT project_id, name, first_frame, last_frame, format, plate_path FROM shots WHERE project_id = ? ORDER BY name ASC"
+    ).map_err(|e| e.to_string())?;
+    let shots = stmt.query_map(params![project_id], map_shot)
+        .map_err(|e| e.to_string())?
+        .collect::<rusqlite::Result<Vec<Shot>>>()
+        .map_err(|e| e.to_string())?;
+    Ok(shots)
+}
+
+#[tauri::command]
+pub fn set_shot_frame_range(
+    project_id: i64,
+    shot_name: String,
+    first_frame: Option<i64>,
+    last_frame: Option<i64>,
+    acting_user_id: i64
+) -> Result<bool, String> {
+    if let (Some(first), Some(last)) = (first_frame, last_frame) {
+        if first > last {
+            return Err(format!("First frame {} is after last frame {}", first, last));
+        }
+    }
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    access::require_project_owner(&conn, project_id, acting_user_id)?;
+
+    let now = Utc::now().to_rfc3339();
+    conn.execute(
+        "INSERT INTO shots (project_id, name, first_frame, last_frame, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?)
+         ON CONFLICT(project_id, name) DO UPDATE SET first_frame = excluded.first_frame, last_frame = excluded.last_frame, updated_at = excluded.updated_at",
+        params![project_id, shot_name, first_frame, last_frame, now, now],
+    ).map_err(|e| e.to_string())?;
+
+    logger::info(&format!("Shot {} in project {} frame range set to {:?}-{:?}", shot_name, project_id, first_frame, last_frame));
+    Ok(true)
+}
+
+#[tauri::command]
+pub fn set_shot_details(
+    project_id: i64,
+    shot_name: String,
+    format: Option<String>,
+    plate_path: Option<String>,
+    acting_user_id: i64
+) -> Result<bool, String> {
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    access::require_project_owner(&conn, project_id, acting_user_id)?;
+
+    let now = Utc::now().to_rfc3339();
+    conn.execute(
+        "INSERT INTO shots (project_id, name, format, plate_path, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?)
+         ON CONFLICT(project_id, name) DO UPDATE SET format = excluded.format, plate_path = excluded.plate_path, updated_at = excluded.updated_at",
+        params![project_id, shot_name, format, plate_path.as_deref().map(paths::canonical_path), now, now],
+    ).map_err(|e| e.to_string())?;
+
+    logger::info(&format!("Shot {} in project {} format set to {:?}, plates {:?}", shot_name, project_id, format, plate_path));
+    Ok(true)
+}
@@ -0,0 +1,169 @@
+use std::time::Duration;
+use chrono::{Duration as ChronoDuration, Utc};
+use once_cell::sync::OnceCell;
+use rusqlite::params;
+use serde::{Serialize, Deserialize};
+use tauri::{AppHandle, Emitter};
+use crate::config::{self, DatabaseMode};
+use crate::db;
+use crate::files::FileChanges;
+use crate::logger;
+use crate::paths;
+
+// Event names, listened for with `listen()` from @tauri-apps/api/event
+pub const PROJECT_FILES_CHANGED: &str = "project-files-changed";
+pub const WATCHER_ERROR: &str = "watcher-error";
+
+// Change rows only need to outlive the slowest workstation's poll
+const CHANGE_RETENTION_HOURS: i64 = 24;
+
+static APP_HANDLE: OnceCell<AppHandle> = OnceCell::new();
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ProjectFilesChanged {
+    pub project_id: i64,
+    pub added: Vec<i64>,
+    pub changed: Vec<i64>,
+    pub removed: Vec<i64>,
+    // Workstation that indexed the change
+    pub machine: String,
+    // Picked up from the shared database rather than indexed by this process
+    pub remote: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WatcherError {
+    pub project_id: i64,
+    pub error: String,
+}
+
+// Called from the Tauri setup hook; events published before then are only recorded
+pub fn init(app: AppHandle) {
+    if APP_HANDLE.set(app).is_err() {
+        return;
+    }
+    // With a local database every change is already indexed by this process
+    if config::get_config().database.mode == DatabaseMode::Network {
+        std::thread::spawn(change_feed);
+    }
+}
+
+fn emit<S: Serialize + Clone>(event: &str, payload: S) {
+    if let Some(app) = APP_HANDLE.get() {
+        if let Err(e) = app.emit(event, payload) {
+            logger::warn(&format!("Failed to emit {}: {}", event, e));
+        }
+    }
+}
+
+fn ids_to_json(ids: &[i64]) -> String {
+    serde_json::to_string(ids).unwrap_or_else(|_| "[]".to_string())
+}
+
+fn ids_from_json(json: &str) -> Vec<i64> {
+    serde_json::from_str(json).unwrap_or_default()
+}
+
+// Tell this machine's windows about an index update and record it for the other workstations
+pub fn publish_file_changes(project_id: i64, changes: &FileChanges) {
+    if changes.added.is_empty() && changes.changed.is_empty() && changes.removed.is_empty() {
+        return;
+    }
+    let machine = paths::get_machine_name();
+
+    let recorded = db::get_connection().map_err(|e| e.to_string()).and_then(|conn| {
+        let now = Utc::now();
+        conn.execute(
+            "INSERT INTO project_file_events (project_id, machine, added, changed, removed, created_at) VALUES (?, ?, ?, ?, ?, ?)",
+            params![
+                project_id,
+                machine,
+                ids_to_json(&changes.added),
+                ids_to_json(&changes.changed),
+                ids_to_json(&changes.removed),
+                now.to_rfc3339()
+            ]
+        ).map_err(|e| e.to_string())?;
+        let cutoff = (now - ChronoDuration::hours(CHANGE_RETENTION_HOURS)).to_rfc3339();
+        conn.execute("DELETE FROM project_file_events WHERE created_at < ?", params![cutoff])
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    });
+    if let Err(e) = recorded {
+        logger::warn(&format!("Failed to record file changes for project {}: {}", project_id, e));
+    }
+
+    emit(PROJECT_FILES_CHANGED, ProjectFilesChanged {
+        project_id,
+        added: changes.added.clone(),
+        changed: changes.changed.clone(),
+        removed: changes.removed.clone(),
+        machine,
+        remote: false,
+    });
+}
+
+pub fn publish_watcher_error(project_id: i64, error: &str) {
+    emit(WATCHER_ERROR, WatcherError { project_id, error: error.to_string() });
+}
+
+// Changes recorded by other workstations since `after`, with the id of the newest row
+fn remote_changes_since(after: i64, machine: &str) -> Result<(Vec<ProjectFilesChanged>, i64), String> {
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    let mut stmt = conn.prepare(
+        "SELECT id, project_id, machine, added, changed, removed FROM project_file_events WHERE id > ? ORDER BY id"
+    ).map_err(|e| e.to_string())?;
+    let rows = stmt.query_map(params![after], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            row.get::<_, i64>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, String>(3)?,
+            row.get::<_, String>(4)?,
+            row.get::<_, String>(5)?,
+        ))
+    }).map_err(|e| e.to_string())?;
+
+    let mut last_id = after;
+    let mut changes = Vec::new();
+    for row in rows {
+        let (id, project_id, row_machine, added, changed, removed) = row.map_err(|e| e.to_string())?;
+        last_id = id;
+        if row_machine == machine {
+            continue;
+        }
+        changes.push(ProjectFilesChanged {
+            project_id,
+            added: ids_from_json(&added),
+            changed: ids_from_json(&changed),
+            removed: ids_from_json(&removed),
+            machine: row_machine,
+            remote: true,
+        });
+    }
+    Ok((changes, last_id))
+}
+
+// Re-emit changes other workstations wrote to the shared database. Starts from the newest
+// row so a freshly opened window, which loads the current index anyway, is not replayed history.
+fn change_feed() {
+    let machine = paths::get_machine_name();
+    let mut last_id: i64 = db::get_connection()
+        .and_then(|conn| conn.query_row("SELECT COALESCE(MAX(id), 0) FROM project_file_events", [], |row| row.get(0)))
+        .unwrap_or(0);
+    logger::info(&format!("Following file changes from other workstations after event {}", last_id));
+
+    loop {
+        std::thread::sleep(Duration::from_secs(config::get_config().watcher.change_feed_secs.max(1)));
+        match remote_changes_since(last_id, &machine) {
+            Ok((changes, newest)) => {
+                last_id = newest;
+                for change in changes {
+                    logger::debug(&format!("Project {} changed on {}", change.project_id, change.machine));
+                    emit(PROJECT_FILES_CHANGED, change);
+                }
+            },
+            Err(e) => logger::warn(&format!("Failed to read file changes from the shared database: {}", e)),
+        }
+    }
+}
@@ -1,73 +1,44 @@
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::time::SystemTime;
-use crate::db::ProjectFile;
+use serde::{Serialize, Deserialize};
+use crate::db::{self, ProjectFile};
+use crate::access;
+use crate::applications;
+use crate::environment;
+use crate::events;
+use crate::locks;
+use crate::nuke_paths;
+use crate::sessions;
+use crate::session_logs;
+use crate::versions;
 use crate::logger;
-use crate::paths;
+use crate::paths::{self, OsType};
 use chrono::Utc;
-use rusqlite::params; // Removed unused Connection import
+use rusqlite::{params, OptionalExtension};
 use regex::Regex;
 
 // Scan project directory for files
 #[tauri::command]
 pub fn scan_project(project_id: i64, project_path: String, include_patterns: Vec<String>, scan_dirs: Vec<String>) -> Result<Vec<ProjectFile>, String> {
-    let path = Path::new(&project_path);
+    scan_into_index(project_id, &project_path, &include_patterns, scan_dirs).map(|(files, _)| files)
+}
+
+// Full rescan for the watcher, which only needs to know what changed
+pub fn rescan_project(project_id: i64, project_path: &str, include_patterns: &[String], scan_dirs: Vec<String>) -> Result<FileChanges, String> {
+    scan_into_index(project_id, project_path, include_patterns, scan_dirs).map(|(_, changes)| changes)
+}
+
+fn scan_into_index(project_id: i64, project_path: &str, include_patterns: &[String], scan_dirs: Vec<String>) -> Result<(Vec<ProjectFile>, FileChanges), String> {
+    let path = Path::new(project_path);
     if !path.exists() || !path.is_dir() {
         return Err(format!("Project path does not exist or is not a directory: {}", project_path));
     }
     
     logger::info(&format!("Scanning project at: {}", project_path));
     
-    // Compile regex patterns
-    let mut patterns = Vec::new();
-    let mut has_nk = false;
-    let mut has_aep = false;
-    
-    // Process include patterns
-    for pattern_str in &include_patterns {
-        logger::info(&format!("Processing include pattern: {}", pattern_str));
-        
-        // Check if pattern includes .nk or .aep files
-        if pattern_str.contains(".nk") {
-            has_nk = true;
-        }
-        if pattern_str.contains(".aep") {
-            has_aep = true;
-        }
-        
-        // Compile regex
-        match Regex::new(pattern_str) {
-            Ok(regex) => {
-                logger::info(&format!("Added pattern: {}", pattern_str));
-                patterns.push(regex);
-            }
-            Err(e) => {
-                logger::warn(&format!("Invalid regex pattern {}: {}", pattern_str, e));
-                // Continue with other patterns
-            }
-        }
-    }
-    
-    // Add default patterns if not already included
-    if !has_nk {
-        logger::info("Adding default pattern for .nk files");
-        if let Ok(regex) = Regex::new(r"\.nk$") {
-            patterns.push(regex);
-        }
-    }
-    
-    if !has_aep {
-        logger::info("Adding default pattern for .aep files");
-        if let Ok(regex) = Regex::new(r"\.aep$") {
-            patterns.push(regex);
-        }
-    }
-    
-    // Print all patterns for debugging
-    for (i, pattern) in patterns.iter().enumerate() {
-        logger::info(&format!("Pattern {}: {}", i, pattern));
-    }
-    
+    let patterns = compile_patterns(include_patterns);
     logger::info(&format!("Using {} file patterns", patterns.len()));
     
     // Use provided scan_dirs or default to common VFX directories if empty
@@ -87,10 +58,15 @@ pub fn scan_project(project_id: i64, project_path: String, include_patterns: Vec
     
     logger::info(&format!("Looking for these target directories: {:?}", scan_dirs));
     
+    // Directories that could not be read. Files under them were not seen, so with any
+    // errors nothing is flagged missing; an unreachable share must not empty the index.
+    let mut walk_errors: Vec<String> = Vec::new();
+
     // Find all target folders in the directory structure based on scan_dirs
     let mut project_folders = Vec::new();
     if let Err(e) = find_project_folders(path, &mut project_folders, &scan_dirs) {
         logger::warn(&format!("Error finding project folders: {}", e));
+        walk_errors.push(e);
         // Continue anyway with empty project_folders
     }
     
@@ -104,6 +80,7 @@ pub fn scan_project(project_id: i64, project_path: String, include_patterns: Vec
         
         if let Err(e) = walk_dir(project_folder, path, &patterns, project_id, &mut found_files) {
             logger::warn(&format!("Error scanning directory {}: {}", project_folder.display(), e));
+            walk_errors.push(e);
             // Continue with other folders even if one fails
         }
     }
@@ -115,23 +92,85 @@ pub fn scan_project(project_id: i64, project_path: String, include_patterns: Vec
         logger::warn("Consider adding appropriate target directories to scan_dirs in settings.");
         if let Err(e) = walk_dir(path, path, &patterns, project_id, &mut found_files) {
             logger::warn(&format!("Error walking root directory: {}", e));
+            walk_errors.push(e);
         }
     }
     
     logger::info(&format!("Found {} files", found_files.len()));
     
     // Store files in database
-    match store_files(project_id, &found_files) {
-        Ok(_) => logger::info(&format!("Successfully stored {} files in database", found_files.len())),
+    let changes = match store_files(project_id, &found_files, walk_errors.is_empty()) {
+        Ok(changes) => {
+            logger::info(&format!("Successfully stored {} files in database", found_files.len()));
+            events::publish_file_changes(project_id, &changes);
+            changes
+        },
         Err(e) => {
             let err_msg = format!("Error storing files in database: {}", e);
             logger::error(&err_msg);
             return Err(err_msg);
         }
-    }
+    };
     
+    if !walk_errors.is_empty() {
+        return Err(format!("Indexed {} files, but some directories could not be read: {}", found_files.len(), walk_errors.join("; ")));
+    }
     logger::info("Scan completed successfully");
-    Ok(found_files)
+    Ok((found_files, changes))
+}
+
+// Regexes for the include patterns, plus .nk and .aep when they are not covered
+fn compile_patterns(include_patterns: &[String]) -> Vec<Regex> {
+    let mut patterns = Vec::new();
+    let mut has_nk = false;
+    let mut has_aep = false;
+    
+    // Process include patterns
+    for pattern_str in include_patterns {
+        logger::info(&format!("Processing include pattern: {}", pattern_str));
+        
+        // Check if pattern includes .nk or .aep files
+        if pattern_str.contains(".nk") {
+            has_nk = true;
+        }
+        if pattern_str.contains(".aep") {
+            has_aep = true;
+        }
+        
+        // Compile regex
+        match Regex::new(pattern_str) {
+            Ok(regex) => {
+                logger::info(&format!("Added pattern: {}", pattern_str));
+                patterns.push(regex);
+            }
+            Err(e) => {
+                logger::warn(&format!("Invalid regex pattern {}: {}", pattern_str, e));
+                // Continue with other patterns
+            }
+        }
+    }
+    
+    // Add default patterns if not already included
+    if !has_nk {
+        logger::info("Adding default pattern for .nk files");
+        if let Ok(regex) = Regex::new(r"\.nk$") {
+            patterns.push(regex);
+        }
+    }
+    
+    if !has_aep {
+        logger::info("Adding default pattern for .aep files");
+        if let Ok(regex) = Regex::new(r"\.aep$") {
+            patterns.push(regex);
+        }
+    }
+    
+    // Print all patterns for debugging
+    for (i, pattern) in patterns.iter().enumerate() {
+        logger::info(&format!("Pattern {}: {}", i, pattern));
+    }
+    
+    patterns
 }
 
 // Find specific folder names at the root level, then only scan for files inside those folders
@@ -163,10 +202,7 @@ fn find_project_folders(dir: &Path, project_folders: &mut Vec<PathBuf>, scan_dir
     for entry_result in entries {
         let entry = match entry_result {
             Ok(entry) => entry,
-            Err(e) => {
-                logger::warn(&format!("Failed to read directory entry: {}", e));
-                continue;
-            }
+            Err(e) => return Err(format!("Failed to read directory entry in {}: {}", dir.display(), e)),
         };
         
         let path = entry.path();
@@ -256,8 +292,9 @@ fn walk_dir(
 ) -> Result<(), String> {
     logger::info(&format!("Scanning for VFX files in target directory: {}", dir.display()));
     
-    // Recursive function to scan directories and process files
-    fn scan_directory(dir: &Path, project_root: &Path, patterns: &[Regex], project_id: i64, found_files: &mut Vec<ProjectFile>) -> Result<(), String> {
+    // Recursive function to scan directories and process files. Unreadable directories are
+    // collected in `errors` so the rest of the tree is still walked.
+    fn scan_directory(dir: &Path, project_root: &Path, patterns: &[Regex], project_id: i64, found_files: &mut Vec<ProjectFile>, errors: &mut Vec<String>) -> Result<(), String> {
         logger::debug(&format!("Scanning directory: {}", dir.display()));
         
         // First, check if this directory contains more than one .exr file
@@ -285,7 +322,9 @@ fn walk_dir(
         let entries = match fs::read_dir(dir) {
             Ok(entries) => entries,
             Err(e) => {
-                logger::warn(&format!("Failed to read directory {}: {}", dir.display(), e));
+                let err_msg = format!("Failed to read directory {}: {}", dir.display(), e);
+                logger::warn(&err_msg);
+                errors.push(err_msg);
                 return Ok(());
             }
         };
@@ -294,7 +333,9 @@ fn walk_dir(
             let entry = match entry_result {
                 Ok(entry) => entry,
                 Err(e) => {
-                    logger::warn(&format!("Failed to read directory entry: {}", e));
+                    let err_msg = format!("Failed to read directory entry in {}: {}", dir.display(), e);
+                    logger::warn(&err_msg);
+                    errors.push(err_msg);
                     continue;
                 }
             };
@@ -310,84 +351,10 @@ fn walk_dir(
                     if file_name.ends_with(".nk") || file_name.ends_with(".aep") {
                         logger::info(&format!("Found VFX file by direct extension check: {}", file_name));
                         
-                        // Get relative path from project root
-                        let relative_path = match path.strip_prefix(project_root) {
-                            Ok(rel_path) => rel_path.to_string_lossy().to_string(),
-                            Err(e) => {
-                                logger::warn(&format!("Failed to get relative path for {}: {}", path.display(), e));
-                                continue;
-                            }
-                        };
-                        
-                        // Get parent folder
-                        let parent_folder = path.parent()
-                            .and_then(|p| p.strip_prefix(project_root).ok())
-                            .map(|p| p.to_string_lossy().to_string())
-                            .unwrap_or_default();
-                        
-                        // Extract file type from extension
-                        let file_type = path.extension()
-                            .and_then(|ext| ext.to_str())
-                            .unwrap_or("unknown")
-                            .to_lowercase();
-                        
-                        // Get file metadata
-                        let metadata = match fs::metadata(&path) {
-                            Ok(meta) => meta,
-                            Err(e) => {
-                                logger::warn(&format!("Failed to get metadata for {}: {}", path.display(), e));
-                                continue;
-                            }
-                        };
-                        
-                        let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
-                        
-                        // Extract filename without extension
-                        let filename_without_ext = path.file_stem()
-                            .and_then(|n| n.to_str())
-                            .unwrap_or(file_name)
-                            .to_string();
-                        
-                        // Extract version from filename (if present)
-                        let version_regex = Regex::new(r"v(\d+)$").unwrap();
-                        let version = version_regex.captures(&filename_without_ext)
-                            .and_then(|caps| caps.get(1))
-                            .map(|m| m.as_str().to_string())
-                            .unwrap_or_else(|| "1".to_string());
-                        
-                        // Normalize filename by removing version information
-                        let normalized_filename = version_regex.replace(&filename_without_ext, "").trim_end_matches('_').to_string();
-                        
-                        // Try to extract shot name from parent folder structure
-                        let shot_name = extract_shot_name(&parent_folder);
-                        
-                        // Store version for logging before moving it to the struct
-                        let version_for_log = version.clone();
-                        
-                        // Create ProjectFile
-                        let project_file = ProjectFile {
-                            id: 0, // Will be set by database
-                            project_id,
-                            filename: normalized_filename.clone(), // Use normalized filename without version
-                            version,
-                            file_type: file_type.clone(),
-                            path: path.to_string_lossy().to_string(),
-                            relative_path,
-                            parent_folder,
-                            shot_name,
-                            last_modified: chrono::DateTime::from_timestamp(
-                                modified.duration_since(SystemTime::UNIX_EPOCH)
-                                    .map(|d| d.as_secs() as i64)
-                                    .unwrap_or(0), 0
-                            )
-                            .map(|dt| dt.naive_utc())
-                            .unwrap_or_else(|| chrono::Utc::now().naive_utc())
-                            .to_string(),
-                            created_at: Utc::now().to_string(),
-                        };
-                        
-                        logger::info(&format!("Adding file: {} (version: {}) ({})", normalized_filename, version_for_log, file_type));
-                        found_files.push(project_file);
+                        if let Some(project_file) = project_file_for(&path, project_root, project_id) {
+                            logger::info(&format!("Adding file: {} (version: {}) ({})", project_file.filename, project_file.version, project_file.file_type));
+                            found_files.push(project_file);
+                        }
                     }
                 }
             } else if path.is_dir() {
@@ -401,7 +368,7 @@ fn walk_dir(
                 }
                 
                 // Recursively scan subdirectories
-                scan_directory(&path, project_root, patterns, project_id, found_files)?;
+                scan_directory(&path, project_root, patterns, project_id, found_files, errors)?;
             }
         }
         
@@ -409,7 +376,87 @@ fn walk_dir(
     }
     
     // Start the recursive scan from the target directory
-    scan_directory(dir, project_root, patterns, project_id, found_files)
+    let mut errors = Vec::new();
+    scan_directory(dir, project_root, patterns, project_id, found_files, &mut errors)?;
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("; "))
+    }
+}
+
+// Index entry for one script under the project root, as a scan would record it
+pub fn project_file_for(path: &Path, project_root: &Path, project_id: i64) -> Option<ProjectFile> {
+    let file_name = path.file_name().and_then(|n| n.to_str())?;
+    
+    // Get relative path from project root
+    let relative_path = match path.strip_prefix(project_root) {
+        Ok(rel_path) => rel_path.to_string_lossy().to_string(),
+        Err(e) => {
+            logger::warn(&format!("Failed to get relative path for {}: {}", path.display(), e));
+            return None;
+        }
+    };
+    
+    // Get parent folder
+    let parent_folder = path.parent()
+        .and_then(|p| p.strip_prefix(project_root).ok())
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_default();
+    
+    // Extract file type from extension
+    let file_type = path.extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("unknown")
+        .to_lowercase();
+    
+    // Get file metadata
+    let metadata = match fs::metadata(path) {
+        Ok(meta) => meta,
+        Err(e) => {
+            logger::warn(&format!("Failed to get metadata for {}: {}", path.display(), e));
+            return None;
+        }
+    };
+    
+    let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+    
+    // Extract filename without extension
+    let filename_without_ext = path.file_stem()
+        .and_then(|n| n.to_str())
+        .unwrap_or(file_name)
+        .to_string();
+    
+    // Extract version from filename (if present) and strip it from the name
+    let (normalized_filename, version) = match versions::split_version(&filename_without_ext) {
+        Some((prefix, digits)) => (prefix.trim_end_matches('_').to_string(), digits.to_string()),
+        None => (filename_without_ext.clone(), "1".to_string()),
+    };
+    
+    // Try to extract shot name from parent folder structure
+    let shot_name = extract_shot_name(&parent_folder);
+    
+    Some(ProjectFile {
+        id: 0, // Will be set by database
+        project_id,
+        filename: normalized_filename, // Use normalized filename without version
+        version,
+        file_type,
+        path: path.to_string_lossy().to_string(),
+        relative_path,
+        parent_folder,
+        shot_name,
+        last_modified: chrono::DateTime::from_timestamp(
+            modified.duration_since(SystemTime::UNIX_EPOCH)
+                .map(|d| d.as_secs() as i64)
+                .unwrap_or(0), 0
+        )
+        .map(|dt| dt.naive_utc())
+        .unwrap_or_else(|| chrono::Utc::now().naive_utc())
+        .to_string(),
+        created_at: Utc::now().to_string(),
+        missing: false,
+    })
 }
 
 // Helper function to extract shot name from folder path
@@ -439,12 +486,15 @@ fn extract_shot_name(folder_path: &str) -> Option<String> {
     None
 }
 
-fn store_files(project_id: i64, files: &[ProjectFile]) -> Result<(), String> {
+// `complete` is false when part of the project could not be walked; files not seen are then
+// left as they are instead of being flagged missing
+fn store_files(project_id: i64, files: &[ProjectFile], complete: bool) -> Result<FileChanges, String> {
     logger::info(&format!("Storing {} files for project {}", files.len(), project_id));
     
+    let mut changes = FileChanges::default();
     if files.is_empty() {
         logger::info("No files to store");
-        return Ok(());
+        return Ok(changes);
     }
     
     // Use the database path from the paths module for consistency across the application
@@ -468,84 +518,396 @@ fn store_files(project_id: i64, files: &[ProjectFile]) -> Result<(), String> {
     // Begin transaction
     let tx = conn.transaction().map_err(|e| e.to_string())?;
     
-    // First, clear existing files for this project
-    logger::debug(&format!("Clearing existing files for project {}", project_id));
-    tx.execute(
-        "DELETE FROM project_files WHERE project_id = ?",
-        params![project_id],
-    ).map_err(|e| format!("Failed to clear existing files: {}", e))?;
+    // Update rows in place by path so file ids (and the locks and activity that reference them)
+    // survive a rescan; files that disappeared are flagged missing. Paths are compared in canonical
+    // form so a rescan from another OS matches the same rows.
+    let mut existing: HashMap<String, (i64, String, bool)> = HashMap::new();
+    {
+        let mut select = tx.prepare("SELECT id, path, last_modified, missing FROM project_files WHERE project_id = ?")
+            .map_err(|e| format!("Failed to prepare select statement: {}", e))?;
+        let rows = select.query_map(params![project_id], |row| Ok((
+            row.get::<_, String>(1)?,
+            (row.get::<_, i64>(0)?, row.get::<_, String>(2)?, row.get::<_, bool>(3)?)
+        ))).map_err(|e| format!("Failed to load existing files: {}", e))?;
+        for row in rows {
+            let (path, file) = row.map_err(|e| e.to_string())?;
+            existing.insert(paths::canonical_path(&path), file);
+        }
+    }
     
-    // Prepare statement for inserting files
+    // Prepare statements for updating and inserting files
+    let mut update = tx.prepare(
+        "UPDATE project_files SET filename = ?, version = ?, file_type = ?, path = ?, relative_path = ?, parent_folder = ?, shot_name = ?, last_modified = ?, missing = 0 
+         WHERE id = ?"
+    ).map_err(|e| format!("Failed to prepare update statement: {}", e))?;
     let mut stmt = tx.prepare(
         "INSERT INTO project_files (project_id, filename, version, file_type, path, relative_path, parent_folder, shot_name, last_modified, created_at) 
          VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
     ).map_err(|e| format!("Failed to prepare insert statement: {}", e))?;
     
-    // Insert each file
+    // Insert or update each file
     {
         for file in files {
             logger::debug(&format!("Storing file: {} ({})", file.filename, file.file_type));
+            let path = paths::canonical_path(&file.path);
+            if let Some((id, last_modified, missing)) = existing.remove(&path) {
+                if missing || last_modified != file.last_modified {
+                    changes.changed.push(id);
+                }
+                update.execute(params![
+                    file.filename.clone(),
+                    file.version.clone(),
+                    file.file_type.clone(),
+                    path,
+                    file.relative_path.clone(),
+                    file.parent_folder.clone(),
+                    file.shot_name.clone(),
+                    file.last_modified.clone(),
+                    id
+                ]).map_err(|e| format!("Failed to update file {}: {}", file.filename, e))?;
+                continue;
+            }
             stmt.execute(params![
                 file.project_id,
                 file.filename.clone(),
                 file.version.clone(),
                 file.file_type.clone(),
-                file.path.clone(),
+                path,
                 file.relative_path.clone(),
                 file.parent_folder.clone(),
                 file.shot_name.clone(),
                 file.last_modified.clone(),
                 file.created_at.clone()
             ]).map_err(|e| format!("Failed to insert file {}: {}", file.filename, e))?;
+            changes.added.push(tx.last_insert_rowid());
         }
     }
     
-    // Drop the statement before committing the transaction
+    // Flag files that no longer exist on disk, keeping their ids for activity and sessions
+    if !complete {
+        logger::warn(&format!("Not flagging {} unseen files missing for project {}: the scan was incomplete", existing.len(), project_id));
+        existing.clear();
+    }
+    logger::debug(&format!("Flagging {} stale files missing for project {}", existing.len(), project_id));
+    for (id, _, missing) in existing.values() {
+        if *missing {
+            continue;
+        }
+        tx.execute("UPDATE project_files SET missing = 1 WHERE id = ?", params![id])
+            .map_err(|e| format!("Failed to flag stale file: {}", e))?;
+        changes.removed.push(*id);
+    }
+    
+    // Drop the statements before committing the transaction
+    drop(update);
     drop(stmt);
     
     // Commit transaction
     tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
     
     logger::info(&format!("Successfully stored {} files for project {}", files.len(), project_id));
+    Ok(changes)
+}
+
+// Ids touched by an incremental update
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct FileChanges {
+    pub added: Vec<i64>,
+    pub changed: Vec<i64>,
+    pub removed: Vec<i64>,
+}
+
+// Filesystem changes collected by a watcher, applied without a full scan
+#[derive(Default)]
+pub struct PathChanges {
+    pub upserted: Vec<PathBuf>,
+    pub removed: Vec<PathBuf>,
+    pub renamed: Vec<(PathBuf, PathBuf)>,
+}
+
+// Scripts outside render folders whose name matches one of the project's patterns
+fn is_indexable(path: &Path, project_root: &Path, patterns: &[Regex]) -> bool {
+    let relative = match path.strip_prefix(project_root) {
+        Ok(relative) => relative,
+        Err(_) => return false,
+    };
+    let in_render_dir = relative.parent()
+        .map(|dir| dir.iter().any(|part| {
+            let part = part.to_string_lossy().to_lowercase();
+            part == "render" || part == "renders"
+        }))
+        .unwrap_or(false);
+    let file_name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    path.is_file() && !in_render_dir && patterns.iter().any(|pattern| pattern.is_match(&file_name))
+}
+
+// Ids and stored paths of the indexed files at `path` or below it. The prefix is compared
+// exactly rather than with LIKE, so '_' and '%' in folder names match only themselves and
+// callers can slice the stored path at the prefix length.
+fn indexed_under(conn: &rusqlite::Connection, project_id: i64, path: &Path) -> Result<Vec<(i64, String)>, String> {
+    let canonical = paths::canonical_path(&path.to_string_lossy());
+    let mut stmt = conn.prepare(
+        "SELECT id, path FROM project_files WHERE project_id = ?1
+         AND (path = ?2 OR substr(path, 1, length(?2) + 1) IN (?2 || '/', ?2 || '\\'))"
+    ).map_err(|e| e.to_string())?;
+    let rows = stmt.query_map(params![project_id, canonical], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<rusqlite::Result<Vec<(i64, String)>>>()
+        .map_err(|e| e.to_string())?;
+    Ok(rows)
+}
+
+fn update_indexed_file(conn: &rusqlite::Connection, id: i64, file: &ProjectFile) -> Result<(), String> {
+    conn.execute(
+        "UPDATE project_files SET filename = ?, version = ?, file_type = ?, path = ?, relative_path = ?, parent_folder = ?, shot_name = ?, last_modified = ?, missing = 0
+         WHERE id = ?",
+        params![
+            file.filename,
+            file.version,
+            file.file_type,
+            paths::canonical_path(&file.path),
+            file.relative_path,
+            file.parent_folder,
+            file.shot_name,
+            file.last_modified,
+            id
+        ],
+    ).map_err(|e| format!("Failed to update {}: {}", file.path, e))?;
+    Ok(())
+}
+
+// Index a created or modified file, or every script in a created directory
+fn upsert_path(conn: &rusqlite::Connection, project_id: i64, project_root: &Path, patterns: &[Regex], path: &Path, changes: &mut FileChanges) -> Result<(), String> {
+    let mut found = Vec::new();
+    if path.is_dir() {
+        walk_dir(path, project_root, patterns, project_id, &mut found)?;
+    } else if is_indexable(path, project_root, patterns) {
+        found.extend(project_file_for(path, project_root, project_id));
+    }
+
+    for file in found {
+        let existing: Option<i64> = conn.query_row(
+            "SELECT id FROM project_files WHERE project_id = ? AND path = ?",
+            params![project_id, paths::canonical_path(&file.path)],
+            |row| row.get(0)
+        ).optional().map_err(|e| e.to_string())?;
+        match existing {
+            Some(id) => {
+                update_indexed_file(conn, id, &file)?;
+                changes.changed.push(id);
+            },
+            None => changes.added.push(db::insert_project_file(conn, &file)?),
+        }
+    }
     Ok(())
 }
 
+// Flag the files at or below a removed path; the rows stay so locks and activity keep their file
+fn mark_missing(conn: &rusqlite::Connection, project_id: i64, path: &Path, changes: &mut FileChanges) -> Result<(), String> {
+    for (id, _) in indexed_under(conn, project_id, path)? {
+        let updated = conn.execute("UPDATE project_files SET missing = 1 WHERE id = ? AND missing = 0", params![id])
+            .map_err(|e| e.to_string())?;
+        if updated > 0 {
+            changes.removed.push(id);
+        }
+    }
+    Ok(())
+}
+
+// A rename of a file or folder moves the indexed rows, keeping their ids
+fn move_path(conn: &rusqlite::Connection, project_id: i64, project_root: &Path, patterns: &[Regex], from: &Path, to: &Path, changes: &mut FileChanges) -> Result<(), String> {
+    let moved = indexed_under(conn, project_id, from)?;
+    if moved.is_empty() {
+        return upsert_path(conn, project_id, project_root, patterns, to, changes);
+    }
+
+    let from_len = paths::canonical_path(&from.to_string_lossy()).len();
+    for (id, stored) in moved {
+        let suffix = stored.get(from_len..).unwrap_or_default();
+        let new_path = PathBuf::from(paths::normalize_path(&format!("{}{}", to.to_string_lossy(), suffix)));
+        match project_file_for(&new_path, project_root, project_id).filter(|_| is_indexable(&new_path, project_root, patterns)) {
+            Some(file) => {
+                update_indexed_file(conn, id, &file)?;
+                changes.changed.push(id);
+            },
+            // Renamed to something that is no longer a script, e.g. comp_v003.nk.bak
+            None => {
+                conn.execute("UPDATE project_files SET missing = 1 WHERE id = ?", params![id]).map_err(|e| e.to_string())?;
+                changes.removed.push(id);
+            },
+        }
+    }
+    Ok(())
+}
+
+// Apply a watcher's batch of changes to the index in one transaction
+pub fn apply_path_changes(project_id: i64, project_path: &str, include_patterns: &[String], path_changes: &PathChanges) -> Result<FileChanges, String> {
+    let project_root = PathBuf::from(paths::normalize_path(project_path));
+    let patterns = compile_patterns(include_patterns);
+    let mut conn = crate::db::get_connection().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let mut changes = FileChanges::default();
+    for (from, to) in &path_changes.renamed {
+        move_path(&tx, project_id, &project_root, &patterns, from, to, &mut changes)?;
+    }
+    for path in &path_changes.removed {
+        mark_missing(&tx, project_id, path, &mut changes)?;
+    }
+    for path in &path_changes.upserted {
+        upsert_path(&tx, project_id, &project_root, &patterns, path, &mut changes)?;
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+    logger::info(&format!(
+        "Project {} updated: {} added, {} changed, {} missing",
+        project_id, changes.added.len(), changes.changed.len(), changes.removed.len()
+    ));
+    events::publish_file_changes(project_id, &changes);
+    Ok(changes)
+}
+
+// Find an indexed file's id, project and shot, by id when known and by path otherwise
+fn find_indexed_file(conn: &rusqlite::Connection, file_id: Option<i64>, file_path: &str) -> Result<Option<(i64, i64, Option<String>)>, String> {
+    let result = match file_id {
+        Some(id) => conn.query_row(
+            "SELECT id, project_id, shot_name FROM project_files WHERE id = ?",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        ),
+        None => conn.query_row(
+            "SELECT id, project_id, shot_name FROM project_files WHERE path IN (?, ?) LIMIT 1",
+            params![paths::canonical_path(file_path), file_path],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        ),
+    };
+    
+    match result {
+        Ok(found) => Ok(Some(found)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(format!("Failed to look up project for file: {}", e)),
+    }
+}
+
+// Project whose root contains `file_path`, the deepest one when project folders nest. Used
+// for files the index has not picked up yet, so access checks still apply to them.
+fn project_for_path(conn: &rusqlite::Connection, file_path: &str) -> Result<Option<i64>, String> {
+    let file = paths::canonical_path(file_path).replace('\\', "/");
+    let mut stmt = conn.prepare("SELECT id, path FROM projects").map_err(|e| e.to_string())?;
+    let projects = stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<rusqlite::Result<Vec<(i64, String)>>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut best: Option<(usize, i64)> = None;
+    for (id, path) in projects {
+        let root = paths::canonical_path(&path).replace('\\', "/");
+        let root = root.trim_end_matches('/');
+        let inside = file.strip_prefix(root).map(|rest| rest.is_empty() || rest.starts_with('/')).unwrap_or(false);
+        if !root.is_empty() && inside && best.map(|(len, _)| root.len() > len).unwrap_or(true) {
+            best = Some((root.len(), id));
+        }
+    }
+    Ok(best.map(|(_, id)| id))
+}
+
+// A launched file: its session, and the lock warning to show when opening it anyway
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct OpenedFile {
+    // None when the file was handed to the OS file association, whose launcher exits at once
+    pub session_id: Option<i64>,
+    pub warning: Option<String>,
+}
+
 // Open file in appropriate application
 #[tauri::command]
-pub fn open_file(file_path: String, app_path: String) -> Result<(), String> {
+pub fn open_file(
+    file_path: String,
+    app_path: Option<String>,
+    app_version: Option<String>,
+    user_id: Option<i64>,
+    file_id: Option<i64>,
+    variant: Option<String>
+) -> Result<OpenedFile, String> {
+    // Enforce project membership before launching anything
+    let conn = crate::db::get_connection().map_err(|e| e.to_string())?;
+    let indexed = find_indexed_file(&conn, file_id, &file_path)?;
+    let project_id = match &indexed {
+        Some((_, project_id, _)) => Some(*project_id),
+        None => project_for_path(&conn, &file_path)?,
+    };
+    match project_id {
+        Some(project_id) => access::require_open_access(&conn, project_id, user_id)?,
+        None if !user_id.map(|uid| access::is_admin(&conn, uid)).unwrap_or(false) => {
+            logger::warn(&format!("User {:?} refused {}: not inside any project", user_id, file_path));
+            return Err("Only admins can open files outside the registered projects".to_string());
+        },
+        None => {},
+    }
+    let warning = match &indexed {
+        Some((indexed_file_id, _, _)) => locks::enforce_lock_policy(&conn, *indexed_file_id, user_id)?,
+        None => None,
+    };
+    
     // Convert the file path to the correct format for the current OS
     let normalized_file_path = paths::normalize_path(&file_path);
-    let normalized_app_path = paths::normalize_path(&app_path);
-    
-    logger::info(&format!("Opening file: {} with application: {}", normalized_file_path, normalized_app_path));
     
     // Log file extension for debugging
-    if let Some(extension) = Path::new(&file_path).extension() {
-        if let Some(ext_str) = extension.to_str() {
-            logger::info(&format!("File extension: {}", ext_str));
-        }
-    }
+    let file_type = Path::new(&file_path).extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase())
+        .unwrap_or_default();
+    logger::info(&format!("File extension: {}", file_type));
     
-    if app_path.is_empty() {
-        let err_msg = "Application path is empty";
-        logger::error(err_msg);
-        return Err(err_msg.to_string());
-    }
-    
-    // Check if we're opening a Nuke file (.nk extension)
-    let is_nuke_file = if let Some(extension) = Path::new(&file_path).extension() {
-        if let Some(ext_str) = extension.to_str() {
-            ext_str.to_lowercase() == "nk"
-        } else {
-            false
+    // An explicit application path still wins; otherwise pick from the registry by file type.
+    // Types no registered application opens go to the OS file associations.
+    let app_path = app_path.filter(|p| !p.trim().is_empty());
+    let associated = app_path.is_none() && !applications::is_registered_type(&conn, &file_type)?;
+    let (normalized_app_path, arg_template, application) = match app_path {
+        Some(path) => {
+            // A pinned version may not be sidestepped by naming an executable
+            if let Some(project_id) = project_id {
+                let shot_name = indexed.as_ref().and_then(|(_, _, shot_name)| shot_name.as_deref());
+                if let Some(pin) = applications::launch_pin(&conn, &file_type, project_id, shot_name)? {
+                    return Err(format!("{} is pinned to {} {}; open it without an application path", applications::pin_scope(&pin), pin.application_name, pin.version));
+                }
+            }
+            // A registered executable brings its own arguments and variants
+            match applications::application_for_executable(&conn, &path, &file_type)? {
+                Some(app) => {
+                    let template = applications::argument_template(&conn, &app, variant.as_deref())?;
+                    (paths::normalize_path(&path), template, Some(app))
+                },
+                None if variant.is_some() => {
+                    return Err("Launch variants need an application from the registry".to_string());
+                },
+                None => (paths::normalize_path(&path), applications::default_template(&file_type), None),
+            }
+        },
+        None if associated => {
+            if variant.is_some() {
+                return Err("Launch variants need an application from the registry".to_string());
+            }
+            logger::info(&format!("No application is registered for .{} files, using the system default", file_type));
+            let (opener, args) = default_opener();
+            (opener, args, None)
+        },
+        None => {
+            let shot_name = indexed.as_ref().and_then(|(_, _, shot_name)| shot_name.as_deref());
+            let app = applications::resolve_launch_application(&conn, &file_type, project_id, shot_name, app_version.as_deref()).inspect_err(|e| {
+                logger::error(e);
+            })?;
+            let executable = app.executable_for_current_os().unwrap_or_default();
+            logger::info(&format!("Resolved application {} for .{} file", app.label(), file_type));
+            let template = applications::argument_template(&conn, &app, variant.as_deref())?;
+            (paths::normalize_path(executable), template, Some(app))
         }
-    } else {
-        false
     };
     
-    if is_nuke_file {
-        logger::info("Detected Nuke file, will launch as NukeX");
-    }
+    // Pipeline variables (OCIO, NUKE_PATH, shot context) on top of the inherited environment
+    let indexed_file_id = indexed.as_ref().map(|(id, _, _)| *id);
+    let launch_env = environment::resolve_launch_environment(&conn, indexed_file_id, application.as_ref())?;
+    drop(conn);
     
     // Check if file exists
     if !Path::new(&normalized_file_path).exists() {
@@ -554,188 +916,185 @@ pub fn open_file(file_path: String, app_path: String) -> Result<(), String> {
         return Err(err_msg);
     }
     
-    // Execute the command to open the file
-    #[cfg(target_os = "windows")]
-    {
-        use std::process::Command;
-        let result = if !normalized_app_path.is_empty() {
-            // If app_path is specified, use it directly with proper arguments
-            let mut cmd = Command::new(&normalized_app_path);
-            
-            // If it's a Nuke file, run it as NukeX by adding --nukex argument
-            if is_nuke_file {
-                logger::info("Running Nuke as NukeX on Windows");
-                cmd.arg("--nukex");
-            }
-            
-            cmd.arg(&normalized_file_path)
-               .spawn()
-               .map(|_| ())
-               .map_err(|e| e.to_string())
-        } else {
-            // Use the default association through cmd /c start
-            Command::new("cmd")
-                .args(["/c", "start", "", &normalized_file_path])
-                .spawn()
-                .map(|_| ())
-                .map_err(|e| e.to_string())
-        };
-        match result {
-            Ok(_) => (),
-            Err(e) => {
-                let err_msg = format!("Failed to open file: {}", e);
-                logger::error(&err_msg);
-                return Err(err_msg);
-            }
-        }
+    // Point Read/Write nodes at this machine's paths when [nuke_paths] asks for it
+    let normalized_file_path = if file_type == "nk" {
+        nuke_paths::localize_for_launch(&normalized_file_path, project_id, indexed_file_id, user_id)
+    } else {
+        normalized_file_path
+    };
+    
+    // Fill in {file}, {first}, {last}, ... from the file and its shot context
+    let tokens = applications::argument_tokens(&normalized_file_path, &launch_env);
+    let app_args = applications::expand_arguments(&arg_template, &tokens).inspect_err(|e| {
+        logger::error(e);
+    })?;
+    
+    logger::info(&format!("Opening file: {} with application: {} {:?}", normalized_file_path, normalized_app_path, app_args));
+    for var in &launch_env {
+        logger::debug(&format!("Launch environment: {}={} ({})", var.name, var.value, var.source));
     }
+    let env_pairs: Vec<(&str, &str)> = launch_env.iter().map(|v| (v.name.as_str(), v.value.as_str())).collect();
+    
+    // Build the platform-specific command that launches the application
+    #[cfg(any(target_os = "windows", target_os = "linux"))]
+    let mut command = {
+        let mut command = std::process::Command::new(&normalized_app_path);
+        command.args(&app_args);
+        command
+    };
     
     #[cfg(target_os = "macos")]
-    {
-        use std::process::Command;
+    let mut command = if file_type == "nk" && !associated {
         use std::fs::File;
         use std::io::Write;
         use std::os::unix::fs::PermissionsExt;
         
-        if is_nuke_file && !normalized_app_path.is_empty() {
-            // For Nuke files on macOS, create a temporary shell script that will launch NukeX with the right arguments
-            // This is more reliable than trying to pass args directly to macOS .app bundles
-            logger::info("Creating launcher script for NukeX on macOS");
-            
-            // Create a temporary script to launch NukeX
-            let temp_dir = std::env::temp_dir();
-            let script_path = temp_dir.join("launch_nukex.sh");
-            
-            // Determine if app_path points to the .app bundle or the executable inside
-            let executable_path = if normalized_app_path.ends_with(".app") {
-                // If it's a .app bundle, construct the path to the executable inside
-                format!("{}/Contents/MacOS/Nuke", normalized_app_path)
-            } else {
-                // If it's already pointing to the executable, use as is
-                normalized_app_path.clone()
-            };
-            
-            // Log the paths for debugging
-            logger::info(&format!("NukeX executable path: {}", executable_path));
-            logger::info(&format!("File path: {}", normalized_file_path));
-            
-            // Create script content
-            let script_content = format!("#!/bin/bash\n\n# Launch NukeX with file\n\"{}\" --nukex \"{}\"\n",
-                executable_path, normalized_file_path);
-            
-            // Write the script
-            match File::create(&script_path) {
-                Ok(mut file) => {
-                    if let Err(e) = file.write_all(script_content.as_bytes()) {
-                        let err_msg = format!("Failed to write launch script: {}", e);
-                        logger::error(&err_msg);
-                        return Err(err_msg);
-                    }
-                    
-                    // Make the script executable
-                    if let Err(e) = std::fs::set_permissions(&script_path, std::fs::Permissions::from_mode(0o755)) {
-                        let err_msg = format!("Failed to make script executable: {}", e);
-                        logger::error(&err_msg);
-                        return Err(err_msg);
-                    }
-                    
-                    // Execute the script
-                    logger::info(&format!("Executing script: {}", script_path.display()));
-                    let result = Command::new(&script_path)
-                        .spawn()
-                        .map(|_| ())
-                        .map_err(|e| e.to_string());
-                    
-                    match result {
-                        Ok(_) => {
-                            logger::info("Successfully launched NukeX via script");
-                            return Ok(());
-                        },
-                        Err(e) => {
-                            let err_msg = format!("Failed to execute NukeX launch script: {}", e);
-                            logger::error(&err_msg);
-                            return Err(err_msg);
-                        }
-                    }
-                },
-                Err(e) => {
-                    let err_msg = format!("Failed to create launch script: {}", e);
-                    logger::error(&err_msg);
-                    return Err(err_msg);
-                }
-            }
+        // For Nuke files on macOS, create a temporary shell script that will launch Nuke with the right arguments
+        // This is more reliable than trying to pass args directly to macOS .app bundles
+        logger::info("Creating launcher script for Nuke on macOS");
+        
+        // Create a temporary script to launch Nuke
+        let temp_dir = std::env::temp_dir();
+        let script_path = temp_dir.join("launch_nukex.sh");
+        
+        // Determine if app_path points to the .app bundle or the executable inside
+        let executable_path = if normalized_app_path.ends_with(".app") {
+            // If it's a .app bundle, construct the path to the executable inside
+            format!("{}/Contents/MacOS/Nuke", normalized_app_path)
         } else {
-            // For other files or if no app path, use standard 'open' command
-            logger::info("Using macOS 'open' command for non-Nuke file");
-            
-            let mut args = Vec::new();
-            
-            if !normalized_app_path.is_empty() {
-                // If app_path is specified, use it
-                args.push("-a");
-                args.push(&normalized_app_path);
-            }
-            
-            args.push(&normalized_file_path);
-            
-            match Command::new("open").args(&args).output() {
-                Ok(_) => (),
-                Err(e) => {
-                    let err_msg = format!("Failed to open file: {}", e);
-                    logger::error(&err_msg);
-                    return Err(err_msg);
-                }
-            }
+            // If it's already pointing to the executable, use as is
+            normalized_app_path.clone()
+        };
+        
+        // Log the paths for debugging
+        logger::info(&format!("Nuke executable path: {}", executable_path));
+        logger::info(&format!("File path: {}", normalized_file_path));
+        
+        // `exec` so the tracked child process is Nuke itself rather than the shell
+        let mut command_line = vec![shell_quote(&executable_path)];
+        command_line.extend(app_args.iter().map(|arg| shell_quote(arg)));
+        let script_content = format!("#!/bin/bash\n\n# Launch Nuke with file\nexec {}\n", command_line.join(" "));
+        
+        // Write the script and make it executable
+        let write_result = File::create(&script_path)
+            .and_then(|mut file| file.write_all(script_content.as_bytes()))
+            .and_then(|_| std::fs::set_permissions(&script_path, std::fs::Permissions::from_mode(0o755)));
+        if let Err(e) = write_result {
+            let err_msg = format!("Failed to create launch script: {}", e);
+            logger::error(&err_msg);
+            return Err(err_msg);
         }
-    }
+        
+        logger::info(&format!("Executing script: {}", script_path.display()));
+        std::process::Command::new(&script_path)
+    } else {
+        // For other files use the standard 'open' command; -W keeps it alive until the app quits
+        logger::info("Using macOS 'open' command for non-Nuke file");
+        
+        // Apps started through LaunchServices do not inherit our environment, so pass it explicitly
+        let mut command = std::process::Command::new("open");
+        command.arg("-W");
+        for (name, value) in &env_pairs {
+            command.arg("--env").arg(format!("{}={}", name, value));
+        }
+        // `open` takes the file itself; everything else goes to the application after --args
+        if !associated {
+            command.args(["-a", normalized_app_path.as_str()]);
+        }
+        command.arg(&normalized_file_path);
+        let extra_args: Vec<&String> = app_args.iter().filter(|arg| **arg != normalized_file_path).collect();
+        if !extra_args.is_empty() {
+            command.arg("--args").args(extra_args);
+        }
+        command
+    };
     
-    #[cfg(target_os = "linux")]
-    {
-        use std::process::Command;
-        let result = if !normalized_app_path.is_empty() {
-            // If app_path is specified, use it
-            let mut cmd = Command::new(&normalized_app_path);
-            
-            // If it's a Nuke file, run it as NukeX by adding --nukex argument
-            if is_nuke_file {
-                logger::info("Running Nuke as NukeX on Linux");
-                cmd.arg("--nukex");
-            }
-            
-            cmd.arg(&normalized_file_path)
-               .spawn()
-               .map(|_| ())
-               .map_err(|e| e.to_string())
-        } else {
-            // If no app_path is specified, use the xdg-open command
-            Command::new("xdg-open")
-                .arg(&normalized_file_path)
-                .spawn()
-                .map(|_| ())
-                .map_err(|e| e.to_string())
-        };
-        match result {
-            Ok(_) => (),
+    command.envs(env_pairs.iter().copied());
+    
+    // Capture the application's output so failed launches can be diagnosed later.
+    // On macOS `open` hands non-Nuke apps to LaunchServices, so only its own output is captured.
+    // The OS association launchers (start, open, xdg-open) exit at once, so they get no log.
+    let session_log = if associated {
+        None
+    } else {
+        match session_logs::create_session_log(&command, &launch_env) {
+            Ok(log) => {
+                command.stdout(log.stdio()?).stderr(log.stdio()?);
+                Some(log)
+            },
             Err(e) => {
-                let err_msg = format!("Failed to open file: {}", e);
-                logger::error(&err_msg);
-                return Err(err_msg);
+                logger::warn(&format!("Launching without a session log: {}", e));
+                None
             }
         }
+    };
+    let log_path = session_log.as_ref().map(|log| log.path.to_string_lossy().to_string());
+    
+    let mut child = match command.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            let err_msg = format!("Failed to open file: {}", e);
+            logger::error(&err_msg);
+            return Err(err_msg);
+        }
+    };
+
+    // The launcher's exit says nothing about the application it started, so the launch is
+    // not tracked as a session; the launcher is only reaped
+    if associated {
+        std::thread::spawn(move || child.wait());
+        logger::info(&format!("Opened file with its OS file association: {}", file_path));
+        return Ok(OpenedFile { session_id: None, warning });
     }
     
-    logger::info(&format!("Successfully opened file: {}", file_path));
-    Ok(())
+    // Keep the script checked out while the application has it open
+    let holds_lock = match (&indexed, user_id) {
+        (Some((indexed_file_id, _, _)), Some(uid)) => locks::lock_for_session(*indexed_file_id, uid),
+        _ => false,
+    };
+
+    // Hand the child over to the session tracker, which records its exit and releases the lock
+    let application_label = application.as_ref()
+        .map(|app| app.label())
+        .unwrap_or_else(|| normalized_app_path.clone());
+    let session_id = sessions::register_session(child, sessions::NewSession {
+        user_id,
+        project_id,
+        file_id: indexed_file_id,
+        file_path: &normalized_file_path,
+        application: &application_label,
+        log_path: log_path.as_deref(),
+        holds_lock,
+    })?;
+    
+    logger::info(&format!("Successfully opened file: {} (session {})", file_path, session_id));
+    Ok(OpenedFile { session_id: Some(session_id), warning })
 }
+
+// The OS file association launcher, with an argument template for it
+fn default_opener() -> (String, Vec<String>) {
+    let (opener, args): (&str, &[&str]) = match paths::get_os_type() {
+        // The empty argument is start's window title, so a quoted file path is not taken for one
+        OsType::Windows => ("cmd", &["/C", "start", "", "{file}"]),
+        OsType::MacOS => ("open", &["{file}"]),
+        _ => ("xdg-open", &["{file}"]),
+    };
+    (opener.to_string(), args.iter().map(|arg| arg.to_string()).collect())
+}
+
+// Quote an argument for the generated bash launch script
+#[cfg(target_os = "macos")]
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
 // Simple echo function for testing frontend-backend communication
 #[tauri::command]
 pub fn test_echo(message: String) -> Result<String, String> {
-    println!("BACKEND RECEIVED: {}", message);
     logger::info(&format!("test_echo command received: {}", message));
     
     // Log that we're about to return a response
     let response = format!("ECHO REPLY: {}", message);
-    println!("BACKEND RESPONDING WITH: {}", response);
     logger::info(&format!("test_echo responding with: {}", response));
     
     // Return success result
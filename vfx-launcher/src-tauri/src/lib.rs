@@ -3,11 +3,26 @@ mod db;
 mod templates;
 mod files;
 mod watcher;
+mod poller;
 mod auth;
 mod dialog;
 mod logger;
 mod paths;
 mod config;
+mod access;
+mod locks;
+mod applications;
+mod environment;
+mod sessions;
+mod session_logs;
+mod versions;
+mod scripts;
+mod nuke_paths;
+mod events;
+mod redact;
+mod diagnostics;
+mod activity;
+mod activity_export;
 
 #[tauri::command]
 fn greet(name: &str) -> String {
@@ -16,14 +31,14 @@ fn greet(name: &str) -> String {
 
 #[tauri::command]
 fn debug_test() -> String {
-    println!("Debug test command was called!");
+    logger::debug("Debug test command was called!");
     String::from("Debug test successful")
 }
 
-// NEW Command to print messages from frontend to terminal
+// Messages from the frontend, logged under the "frontend" module
 #[tauri::command]
 fn log_to_terminal(message: String) {
-    println!("FRONTEND_LOG: {}", message);
+    log::info!(target: "frontend", "{}", message);
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -37,12 +52,13 @@ pub fn run() {
     
     // Load configuration
     let cfg = config::load_config();
+    logger::configure(&cfg.logging);
     logger::info("Configuration loaded");
-    logger::info(&format!("Database mode: {}", cfg.database.mode));
+    logger::info(&format!("Database mode: {:?}", cfg.database.mode));
     logger::info(&format!("Network path: {}", cfg.paths.network_base));
     
     // Check for network connectivity if in network mode
-    if cfg.database.mode == "network" {
+    if cfg.database.mode == config::DatabaseMode::Network {
         let db_path = paths::get_network_database_path();
         logger::info(&format!("Checking network database access: {}", db_path.display()));
         
@@ -65,6 +81,9 @@ pub fn run() {
         logger::error(&format!("Error initializing database: {}", e));
     } else {
         logger::info("Database initialized successfully");
+        if let Err(e) = sessions::recover_stale_sessions() {
+            logger::error(&format!("Error recovering launch sessions: {}", e));
+        }
     }
     
     // Initialize users (create admin if none exists)
@@ -76,6 +95,13 @@ pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_shell::init())
+        .setup(|app| {
+            // Lets the watcher and scanner push events to the windows
+            events::init(app.handle().clone());
+            std::thread::spawn(watcher::restore_watches);
+            std::thread::spawn(activity_export::retention_loop);
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             greet,
             debug_test,
@@ -93,6 +119,33 @@ pub fn run() {
             db::get_recent_projects,
             db::get_favorite_projects,
             db::toggle_favorite_project,
+            access::get_project_members,
+            access::set_project_member,
+            access::remove_project_member,
+            applications::get_applications,
+            applications::save_application,
+            applications::delete_application,
+            applications::get_application_pins,
+            applications::set_application_pin,
+            applications::remove_application_pin,
+            applications::get_launch_variants,
+            applications::save_launch_variant,
+            applications::delete_launch_variant,
+            environment::get_environment_variables,
+            environment::save_environment_variable,
+            environment::delete_environment_variable,
+            environment::get_launch_environment,
+            environment::get_shots,
+            environment::set_shot_frame_range,
+            environment::set_shot_details,
+            sessions::get_running_sessions,
+            sessions::get_session,
+            session_logs::get_session_log,
+            versions::version_up,
+            scripts::get_script_templates,
+            scripts::save_script_template,
+            scripts::delete_script_template,
+            scripts::new_shot_script,
             templates::get_project_templates,
             templates::create_project_from_template,
             files::scan_project,
@@ -106,10 +159,33 @@ pub fn run() {
             auth::update_user,
             auth::delete_user,
             dialog::select_project_folder,
-            auth::log_activity,
-            auth::get_activity_logs,
+            activity::log_activity,
+            activity::get_activity,
+            activity::get_opens_per_project_per_day,
+            activity::get_most_active_files,
+            activity::get_user_activity_summary,
+            activity_export::export_activity,
+            activity_export::archive_activity,
+            activity_export::get_activity_archives,
             auth::check_file_usage,
-            paths::convert_to_local_path
+            locks::checkout_file,
+            locks::checkin_file,
+            locks::heartbeat_file_lock,
+            locks::force_release_file_lock,
+            locks::get_file_lock,
+            locks::get_project_locks,
+            config::get_config_sources,
+            config::get_config_errors,
+            config::reload_config,
+            config::get_config_section,
+            config::update_config_section,
+            paths::convert_to_local_path,
+            paths::get_path_mappings,
+            paths::resolve_path,
+            nuke_paths::localize_nuke_script,
+            nuke_paths::localize_project_scripts,
+            diagnostics::tail_logs,
+            diagnostics::export_diagnostics
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
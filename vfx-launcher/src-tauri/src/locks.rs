@@ -0,0 +1,355 @@
+use std::fs;
+use std::path::PathBuf;
+use serde::{Serialize, Deserialize};
+use rusqlite::{params, Connection, OptionalExtension, TransactionBehavior};
+use chrono::{DateTime, Duration, Utc};
+use crate::activity::{self, ActivityType};
+use crate::access;
+use crate::config::{self, LockPolicy};
+use crate::db;
+use crate::logger;
+use crate::paths;
+
+// An explicit check-out of a project file
+#[derive(Serialize, Deserialize, Clone)]
+pub struct FileLock {
+    pub file_id: i64,
+    pub user_id: i64,
+    pub username: String,
+    pub machine: String,
+    pub locked_at: String,
+    pub heartbeat_at: String,
+    pub expires_at: String,
+    pub sidecar_path: Option<String>,
+}
+
+// Contents of the `<file>.lock` sidecar written next to a checked out script
+#[derive(Serialize, Deserialize)]
+struct SidecarLock {
+    username: String,
+    machine: String,
+    locked_at: String,
+}
+
+fn expiry() -> Duration {
+    Duration::minutes(config::get_config().locks.expiry_minutes.max(1))
+}
+
+// Unreadable heartbeats count as expired so a corrupt row cannot hold a file forever
+fn is_expired(heartbeat_at: &str) -> bool {
+    DateTime::parse_from_rfc3339(heartbeat_at)
+        .map(|t| t.with_timezone(&Utc) + expiry() < Utc::now())
+        .unwrap_or(true)
+}
+
+fn expires_at(heartbeat_at: &str) -> String {
+    DateTime::parse_from_rfc3339(heartbeat_at)
+        .map(|t| (t.with_timezone(&Utc) + expiry()).to_rfc3339())
+        .unwrap_or_else(|_| heartbeat_at.to_string())
+}
+
+fn record_activity(conn: &Connection, user_id: i64, activity_type: ActivityType, file_id: i64, details: &str) {
+    let project_id: Option<i64> = conn.query_row(
+        "SELECT project_id FROM project_files WHERE id = ?",
+        params![file_id],
+        |row| row.get(0)
+    ).ok();
+    activity::record_activity(conn, user_id, activity_type, project_id, Some(file_id), details);
+}
+
+fn get_file_info(conn: &Connection, file_id: i64) -> Result<(i64, String), String> {
+    conn.query_row(
+        "SELECT project_id, path FROM project_files WHERE id = ?",
+        params![file_id],
+        |row| Ok((row.get(0)?, row.get(1)?))
+    ).optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("File {} not found", file_id))
+}
+
+fn write_sidecar(file_path: &str, username: &str, machine: &str, locked_at: &str) -> Option<String> {
+    let sidecar = PathBuf::from(format!("{}.lock", paths::normalize_path(file_path)));
+    let contents = SidecarLock {
+        username: username.to_string(),
+        machine: machine.to_string(),
+        locked_at: locked_at.to_string(),
+    };
+    let json = match serde_json::to_string_pretty(&contents) {
+        Ok(json) => json,
+        Err(e) => {
+            logger::warn(&format!("Failed to serialize lock sidecar: {}", e));
+            return None;
+        }
+    };
+    match fs::write(&sidecar, json) {
+        Ok(_) => Some(paths::canonical_path(&sidecar.to_string_lossy())),
+        Err(e) => {
+            // The DB lock is authoritative, so a read-only share should not prevent check-out
+            logger::warn(&format!("Failed to write lock sidecar {}: {}", sidecar.display(), e));
+            None
+        }
+    }
+}
+
+fn remove_sidecar(sidecar_path: &Option<String>) {
+    if let Some(path) = sidecar_path {
+        if let Err(e) = fs::remove_file(paths::normalize_path(path)) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                logger::warn(&format!("Failed to remove lock sidecar {}: {}", path, e));
+            }
+        }
+    }
+}
+
+fn delete_lock(conn: &Connection, lock: &FileLock) -> Result<(), String> {
+    conn.execute("DELETE FROM file_locks WHERE file_id = ?", params![lock.file_id])
+        .map_err(|e| e.to_string())?;
+    remove_sidecar(&lock.sidecar_path);
+    Ok(())
+}
+
+// Return the lock on a file if it is still alive; expired locks are cleaned up on the way
+pub fn active_lock(conn: &Connection, file_id: i64) -> Result<Option<FileLock>, String> {
+    let lock = conn.query_row(
+        "SELECT l.file_id, l.user_id, u.username, l.machine, l.locked_at, l.heartbeat_at, l.sidecar_path
+         FROM file_locks l
+         JOIN users u ON l.user_id = u.id
+         WHERE l.file_id = ?",
+        params![file_id],
+        |row| {
+            let heartbeat_at: String = row.get(5)?;
+            Ok(FileLock {
+                file_id: row.get(0)?,
+                user_id: row.get(1)?,
+                username: row.get(2)?,
+                machine: row.get(3)?,
+                locked_at: row.get(4)?,
+                expires_at: expires_at(&heartbeat_at),
+                heartbeat_at,
+                sidecar_path: row.get::<_, Option<String>>(6)?.map(|path| paths::normalize_path(&path)),
+            })
+        }
+    ).optional().map_err(|e| e.to_string())?;
+
+    match lock {
+        Some(lock) if is_expired(&lock.heartbeat_at) => {
+            logger::info(&format!("Lock on file {} held by {} expired without heartbeat", file_id, lock.username));
+            delete_lock(conn, &lock)?;
+            Ok(None)
+        },
+        other => Ok(other),
+    }
+}
+
+// Apply the configured lock policy before `user_id` opens `file_id`. Under the warn policy
+// the open goes ahead and the warning is returned for the caller to show.
+pub fn enforce_lock_policy(conn: &Connection, file_id: i64, user_id: Option<i64>) -> Result<Option<String>, String> {
+    let lock = match active_lock(conn, file_id)? {
+        Some(lock) if Some(lock.user_id) != user_id => lock,
+        _ => return Ok(None),
+    };
+
+    let msg = format!("File is checked out by {} on {} since {}", lock.username, lock.machine, lock.locked_at);
+    if config::get_config().locks.policy == LockPolicy::Block {
+        logger::warn(&format!("Blocked opening locked file {}: {}", file_id, msg));
+        Err(msg)
+    } else {
+        logger::warn(&format!("Opening locked file {}: {}", file_id, msg));
+        Ok(Some(msg))
+    }
+}
+
+// Take the lock on a file for `user_id`, or refresh one the user already holds. The check
+// and the write share one IMMEDIATE transaction so two users checking out at once cannot
+// replace each other's lock. Returns the lock and whether it was newly taken.
+fn take_lock(conn: &mut Connection, file_id: i64, user_id: i64, file_path: &str) -> Result<(FileLock, bool), String> {
+    let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate).map_err(|e| e.to_string())?;
+    let now = Utc::now().to_rfc3339();
+
+    if let Some(lock) = active_lock(&tx, file_id)? {
+        if lock.user_id != user_id {
+            return Err(format!("File is already checked out by {} on {} since {}", lock.username, lock.machine, lock.locked_at));
+        }
+        tx.execute(
+            "UPDATE file_locks SET heartbeat_at = ? WHERE file_id = ?",
+            params![now, file_id],
+        ).map_err(|e| e.to_string())?;
+        tx.commit().map_err(|e| e.to_string())?;
+        return Ok((FileLock { expires_at: expires_at(&now), heartbeat_at: now, ..lock }, false));
+    }
+
+    let username: String = tx.query_row(
+        "SELECT username FROM users WHERE id = ?",
+        params![user_id],
+        |row| row.get(0)
+    ).map_err(|e| e.to_string())?;
+    let machine = paths::get_machine_name();
+
+    let sidecar_path = if config::get_config().locks.write_sidecar {
+        write_sidecar(file_path, &username, &machine, &now)
+    } else {
+        None
+    };
+
+    let inserted = tx.execute(
+        "INSERT OR REPLACE INTO file_locks (file_id, user_id, machine, locked_at, heartbeat_at, sidecar_path) VALUES (?, ?, ?, ?, ?, ?)",
+        params![file_id, user_id, machine, now, now, sidecar_path],
+    ).and_then(|_| tx.commit());
+    if let Err(e) = inserted {
+        remove_sidecar(&sidecar_path);
+        return Err(e.to_string());
+    }
+
+    Ok((FileLock {
+        file_id,
+        user_id,
+        username,
+        machine,
+        locked_at: now.clone(),
+        expires_at: expires_at(&now),
+        heartbeat_at: now,
+        sidecar_path,
+    }, true))
+}
+
+// Check out a file, or refresh an existing check-out held by the same user
+#[tauri::command]
+pub fn checkout_file(file_id: i64, user_id: i64) -> Result<FileLock, String> {
+    let mut conn = db::get_connection().map_err(|e| e.to_string())?;
+    let (project_id, file_path) = get_file_info(&conn, file_id)?;
+    access::require_open_access(&conn, project_id, Some(user_id))?;
+
+    let (lock, taken) = take_lock(&mut conn, file_id, user_id, &file_path)?;
+    if taken {
+        record_activity(&conn, user_id, ActivityType::CheckoutFile, file_id, &format!("Checked out on {}", lock.machine));
+        logger::info(&format!("File {} checked out by {} on {}", file_id, lock.username, lock.machine));
+    }
+    Ok(lock)
+}
+
+// Check out a file for a launch under [locks] checkout_on_open. Returns whether the session
+// took the lock, and so should heartbeat and release it; a lock the user already held stays
+// theirs, and a file checked out by someone else (warn policy) is opened without one.
+pub fn lock_for_session(file_id: i64, user_id: i64) -> bool {
+    if !config::get_config().locks.checkout_on_open {
+        return false;
+    }
+    let result = db::get_connection().map_err(|e| e.to_string()).and_then(|mut conn| {
+        let (_, file_path) = get_file_info(&conn, file_id)?;
+        let (lock, taken) = take_lock(&mut conn, file_id, user_id, &file_path)?;
+        if taken {
+            record_activity(&conn, user_id, ActivityType::CheckoutFile, file_id, &format!("Checked out on {} while open", lock.machine));
+        }
+        Ok(taken)
+    });
+    result.unwrap_or_else(|e| {
+        logger::info(&format!("Opening file {} without checking it out: {}", file_id, e));
+        false
+    })
+}
+
+// Release the lock a session took once its application exits
+pub fn release_session_lock(file_id: i64, user_id: i64) -> Result<(), String> {
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    if let Some(lock) = active_lock(&conn, file_id)?.filter(|lock| lock.user_id == user_id) {
+        delete_lock(&conn, &lock)?;
+        record_activity(&conn, user_id, ActivityType::CheckinFile, file_id, &format!("Checked in from {} on close", lock.machine));
+    }
+    Ok(())
+}
+
+// Release a check-out held by the user
+#[tauri::command]
+pub fn checkin_file(file_id: i64, user_id: i64) -> Result<bool, String> {
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+
+    match active_lock(&conn, file_id)? {
+        Some(lock) if lock.user_id == user_id => {
+            delete_lock(&conn, &lock)?;
+            record_activity(&conn, user_id, ActivityType::CheckinFile, file_id, &format!("Checked in from {}", paths::get_machine_name()));
+            logger::info(&format!("File {} checked in by {}", file_id, lock.username));
+            Ok(true)
+        },
+        Some(lock) => Err(format!("File is checked out by {}, not by you", lock.username)),
+        None => Ok(false),
+    }
+}
+
+// Keep a check-out alive; false when the user no longer holds the lock. An expired lock is
+// released rather than revived, since someone else may have opened the file meanwhile.
+pub fn heartbeat(conn: &Connection, file_id: i64, user_id: i64) -> Result<bool, String> {
+    match active_lock(conn, file_id)? {
+        Some(lock) if lock.user_id == user_id => {},
+        _ => return Ok(false),
+    }
+    let now = Utc::now().to_rfc3339();
+    let updated = conn.execute(
+        "UPDATE file_locks SET heartbeat_at = ? WHERE file_id = ? AND user_id = ?",
+        params![now, file_id, user_id],
+    ).map_err(|e| e.to_string())?;
+    Ok(updated > 0)
+}
+
+// Heartbeat for a check-out made with checkout_file; launched sessions heartbeat their own
+#[tauri::command]
+pub fn heartbeat_file_lock(file_id: i64, user_id: i64) -> Result<bool, String> {
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    heartbeat(&conn, file_id, user_id)
+}
+
+// Admin-only release of somebody else's lock
+#[tauri::command]
+pub fn force_release_file_lock(file_id: i64, admin_user_id: i64) -> Result<bool, String> {
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    if !access::is_admin(&conn, admin_user_id) {
+        return Err("Only admins can force-release locks".to_string());
+    }
+
+    match active_lock(&conn, file_id)? {
+        Some(lock) => {
+            delete_lock(&conn, &lock)?;
+            record_activity(
+                &conn,
+                admin_user_id,
+                ActivityType::ForceReleaseLock,
+                file_id,
+                &format!("Force-released lock held by {} on {}", lock.username, lock.machine),
+            );
+            logger::warn(&format!("Lock on file {} held by {} force-released by admin {}", file_id, lock.username, admin_user_id));
+            Ok(true)
+        },
+        None => Ok(false),
+    }
+}
+
+// The live lock on a file, for users who can see its project
+#[tauri::command]
+pub fn get_file_lock(file_id: i64, user_id: Option<i64>) -> Result<Option<FileLock>, String> {
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    let (project_id, _) = get_file_info(&conn, file_id)?;
+    access::require_project_access(&conn, project_id, user_id)?;
+    active_lock(&conn, file_id)
+}
+
+// All live locks in a project, for showing lock badges in the file list
+#[tauri::command]
+pub fn get_project_locks(project_id: i64, user_id: Option<i64>) -> Result<Vec<FileLock>, String> {
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    access::require_project_access(&conn, project_id, user_id)?;
+
+    let file_ids: Vec<i64> = conn.prepare(
+        "SELECT l.file_id FROM file_locks l JOIN project_files f ON l.file_id = f.id WHERE f.project_id = ?"
+    ).map_err(|e| e.to_string())?
+      .query_map(params![project_id], |row| row.get(0))
+      .map_err(|e| e.to_string())?
+      .collect::<rusqlite::Result<Vec<i64>>>()
+      .map_err(|e| e.to_string())?;
+
+    let mut locks = Vec::new();
+    for file_id in file_ids {
+        if let Some(lock) = active_lock(&conn, file_id)? {
+            locks.push(lock);
+        }
+    }
+    Ok(locks)
+}
@@ -1,100 +1,366 @@
-use std::fs::{File, OpenOptions};
-use std::io::Write;
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::panic::Location;
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
-use chrono::Local;
+use std::time::{Duration, Instant};
+use chrono::{DateTime, Local, NaiveDate};
+use log::{Level, LevelFilter, Log, Metadata, Record};
 use once_cell::sync::Lazy;
+use regex::Regex;
+use crate::config::{LogFormat, LogRotation, LoggingConfig};
+use crate::paths;
+use crate::redact;
 
-// Global logger instance
-static LOGGER: Lazy<Mutex<Logger>> = Lazy::new(|| {
-    Mutex::new(Logger::new("vfx_launcher.log").unwrap_or_else(|e| {
-        eprintln!("Failed to initialize logger: {}", e);
-        Logger::null_logger()
-    }))
-});
-
-pub struct Logger {
-    file: Option<File>,
-}
-
-impl Logger {
-    pub fn new(filename: &str) -> Result<Self, String> {
-        let log_path = Path::new("logs");
-        if !log_path.exists() {
-            std::fs::create_dir_all(log_path).map_err(|e| format!("Failed to create log directory: {}", e))?;
-        }
-        
-        let log_file_path = log_path.join(filename);
-        let file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(log_file_path)
-            .map_err(|e| format!("Failed to open log file: {}", e))?;
-            
-        Ok(Logger { file: Some(file) })
-    }
-    
-    pub fn null_logger() -> Self {
-        Logger { file: None }
-    }
-    
-    fn write_log(&mut self, level: &str, message: &str) -> Result<(), String> {
+const LOG_NAME: &str = "vfx_launcher";
+
+// Buffered lines are written out at least this often, and at once for warnings and errors
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+// Runs with the defaults until configure() is given the [logging] section, since loading
+// the configuration itself logs
+static SINK: Lazy<Mutex<Sink>> = Lazy::new(|| Mutex::new(Sink::new(LoggingConfig::default())));
+
+static FACADE: Facade = Facade;
+
+struct Sink {
+    settings: LoggingConfig,
+    level: LevelFilter,
+    modules: BTreeMap<String, LevelFilter>,
+    redact: Vec<Regex>,
+    dir: PathBuf,
+    file: Option<BufWriter<File>>,
+    // Day the current file was started, for daily rotation
+    opened_on: NaiveDate,
+    size: u64,
+    last_flush: Instant,
+}
+
+fn parse_level(level: &str) -> LevelFilter {
+    level.parse().unwrap_or(LevelFilter::Info)
+}
+
+// Platform data directory, e.g. ~/.local/share/vfx-launcher/logs, unless [logging] directory is set
+fn resolve_dir(settings: &LoggingConfig) -> PathBuf {
+    paths::data_path(&settings.directory, "logs")
+}
+
+fn extension(format: LogFormat) -> &'static str {
+    match format {
+        LogFormat::Text => "log",
+        LogFormat::Json => "jsonl",
+    }
+}
+
+// Module a record is filtered under: the source file name for this crate's logger::* calls,
+// otherwise the first path segment of the target (e.g. "notify", or "watcher" for
+// "vfx_launcher_lib::watcher")
+fn module_of(target: &str) -> &str {
+    let target = target.strip_prefix("vfx_launcher_lib::").unwrap_or(target);
+    target.split("::").next().unwrap_or(target)
+}
+
+impl Sink {
+    fn new(settings: LoggingConfig) -> Self {
+        let mut sink = Sink {
+            level: LevelFilter::Info,
+            modules: BTreeMap::new(),
+            redact: Vec::new(),
+            dir: resolve_dir(&settings),
+            file: None,
+            opened_on: Local::now().date_naive(),
+            size: 0,
+            last_flush: Instant::now(),
+            settings,
+        };
+        sink.apply_settings();
+        sink.open();
+        sink
+    }
+
+    fn apply_settings(&mut self) {
+        self.level = parse_level(&self.settings.level);
+        self.modules = self.settings.modules.iter()
+            .map(|(module, level)| (module.clone(), parse_level(level)))
+            .collect();
+        self.redact = self.settings.redact.iter()
+            .filter_map(|pattern| Regex::new(pattern).ok())
+            .collect();
+    }
+
+    // Most verbose level any module logs at, so the log macros do not drop records early
+    fn max_level(&self) -> LevelFilter {
+        self.modules.values().copied().fold(self.level, std::cmp::max)
+    }
+
+    fn enabled(&self, level: Level, target: &str) -> bool {
+        level <= *self.modules.get(module_of(target)).unwrap_or(&self.level)
+    }
+
+    fn current_path(&self) -> PathBuf {
+        self.dir.join(format!("{}.{}", LOG_NAME, extension(self.settings.format)))
+    }
+
+    fn open(&mut self) {
+        self.file = None;
+        if let Err(e) = fs::create_dir_all(&self.dir) {
+            eprintln!("Failed to create log directory {}: {}", self.dir.display(), e);
+            return;
+        }
+        let path = self.current_path();
+        // An existing file counts from the day it was last written, so a log left over from
+        // yesterday is rotated at startup
+        if let Ok(metadata) = fs::metadata(&path) {
+            self.size = metadata.len();
+            self.opened_on = metadata.modified()
+                .map(|modified| DateTime::<Local>::from(modified).date_naive())
+                .unwrap_or_else(|_| Local::now().date_naive());
+        } else {
+            self.size = 0;
+            self.opened_on = Local::now().date_naive();
+        }
+        match OpenOptions::new().create(true).append(true).open(&path) {
+            Ok(file) => self.file = Some(BufWriter::new(file)),
+            Err(e) => eprintln!("Failed to open log file {}: {}", path.display(), e),
+        }
+    }
+
+    fn needs_rotation(&self, incoming: usize) -> bool {
+        match self.settings.rotation {
+            LogRotation::Daily => Local::now().date_naive() != self.opened_on,
+            LogRotation::Size => self.size > 0 && self.size + incoming as u64 > self.settings.max_size_mb * 1024 * 1024,
+            LogRotation::Never => false,
+        }
+    }
+
+    // Rename the current file after the day it covers (daily) or the time it filled up
+    // (size), then drop the oldest rotated files beyond keep_files
+    fn rotate(&mut self) {
+        if let Some(mut file) = self.file.take() {
+            let _ = file.flush();
+        }
+        let ext = extension(self.settings.format);
+        let stamp = match self.settings.rotation {
+            LogRotation::Daily => self.opened_on.format("%Y-%m-%d").to_string(),
+            _ => Local::now().format("%Y-%m-%d_%H%M%S").to_string(),
+        };
+        let mut rotated = self.dir.join(format!("{}.{}.{}", LOG_NAME, stamp, ext));
+        let mut n = 1;
+        while rotated.exists() {
+            rotated = self.dir.join(format!("{}.{}-{}.{}", LOG_NAME, stamp, n, ext));
+            n += 1;
+        }
+        if let Err(e) = fs::rename(self.current_path(), &rotated) {
+            eprintln!("Failed to rotate log file: {}", e);
+        }
+        self.prune();
+        self.open();
+    }
+
+    fn prune(&self) {
+        let mut rotated: Vec<(std::time::SystemTime, PathBuf)> = rotated_files(&self.dir)
+            .into_iter()
+            .map(|path| (fs::metadata(&path).and_then(|m| m.modified()).unwrap_or(std::time::UNIX_EPOCH), path))
+            .collect();
+        rotated.sort_by_key(|file| std::cmp::Reverse(file.0));
+        for (_, path) in rotated.into_iter().skip(self.settings.keep_files) {
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    fn format(&self, record: &Record, module: &str, message: &str) -> String {
+        match self.settings.format {
+            LogFormat::Text => format!(
+                "[{}] [{}] [{}] {}\n",
+                Local::now().format("%Y-%m-%d %H:%M:%S%.3f"), record.level(), module, message
+            ),
+            LogFormat::Json => format!("{}\n", serde_json::json!({
+                "timestamp": Local::now().to_rfc3339(),
+                "level": record.level().to_string(),
+                "module": module,
+                "message": message,
+            })),
+        }
+    }
+
+    fn write(&mut self, record: &Record) {
+        let module = module_of(record.target()).to_string();
+        // Secrets are masked before the line reaches the console or the file
+        let message = record.args().to_string();
+        let message = redact::redact(&message, &self.redact);
+        let line = self.format(record, &module, &message);
+
+        if self.settings.console {
+            let console = match self.settings.format {
+                LogFormat::Text => line.trim_end().to_string(),
+                LogFormat::Json => format!("[{}] [{}] {}", record.level(), module, message),
+            };
+            if record.level() == Level::Error {
+                eprintln!("{}", console);
+            } else {
+                println!("{}", console);
+            }
+        }
+
+        if self.needs_rotation(line.len()) {
+            self.rotate();
+        }
+        let urgent = record.level() <= Level::Warn;
         if let Some(file) = &mut self.file {
-            let timestamp = Local::now().format("%Y-%m-%d %H:%M:%S%.3f").to_string();
-            let log_line = format!("[{}] [{}] {}\n", timestamp, level, message);
-            
-            file.write_all(log_line.as_bytes())
-                .map_err(|e| format!("Failed to write to log file: {}", e))?;
-                
-            file.flush()
-                .map_err(|e| format!("Failed to flush log file: {}", e))?;
+            if let Err(e) = file.write_all(line.as_bytes()) {
+                eprintln!("Failed to write to log file: {}", e);
+                return;
+            }
+            self.size += line.len() as u64;
+            if urgent || self.last_flush.elapsed() >= FLUSH_INTERVAL {
+                let _ = file.flush();
+                self.last_flush = Instant::now();
+            }
         }
-        
-        // Also print to console for development
-        match level {
-            "ERROR" => eprintln!("[{}] {}", level, message),
-            _ => println!("[{}] {}", level, message),
+    }
+
+    fn flush(&mut self) {
+        if let Some(file) = &mut self.file {
+            let _ = file.flush();
         }
-        
-        Ok(())
+        self.last_flush = Instant::now();
     }
 }
 
-// Public logging functions
-pub fn info(message: &str) {
-    if let Ok(mut logger) = LOGGER.lock() {
-        if let Err(e) = logger.write_log("INFO", message) {
-            eprintln!("Logging error: {}", e);
+// Rotated logs in `dir`, in no particular order
+fn rotated_files(dir: &Path) -> Vec<PathBuf> {
+    let current = [format!("{}.log", LOG_NAME), format!("{}.jsonl", LOG_NAME)];
+    fs::read_dir(dir)
+        .map(|entries| entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| {
+                let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+                name.starts_with(&format!("{}.", LOG_NAME)) && !current.contains(&name)
+            })
+            .collect())
+        .unwrap_or_default()
+}
+
+// Receives records from the `log` facade, so dependencies' log output lands in the same file
+struct Facade;
+
+impl Log for Facade {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        SINK.lock().map(|sink| sink.enabled(metadata.level(), metadata.target())).unwrap_or(false)
+    }
+
+    fn log(&self, record: &Record) {
+        if let Ok(mut sink) = SINK.lock() {
+            if sink.enabled(record.level(), record.target()) {
+                sink.write(record);
+            }
         }
     }
-}
 
-pub fn warn(message: &str) {
-    if let Ok(mut logger) = LOGGER.lock() {
-        if let Err(e) = logger.write_log("WARN", message) {
-            eprintln!("Logging error: {}", e);
+    fn flush(&self) {
+        if let Ok(mut sink) = SINK.lock() {
+            sink.flush();
         }
     }
 }
 
+// Send a message through the facade under the calling file's name, e.g. "watcher"
+#[track_caller]
+fn log_from_caller(level: Level, message: &str) {
+    let caller = Location::caller();
+    let module = Path::new(caller.file())
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    log::logger().log(&Record::builder()
+        .level(level)
+        .target(&module)
+        .file(Some(caller.file()))
+        .line(Some(caller.line()))
+        .args(format_args!("{}", message))
+        .build());
+}
+
+// Public logging functions
+#[track_caller]
+pub fn info(message: &str) {
+    log_from_caller(Level::Info, message);
+}
+
+#[track_caller]
+pub fn warn(message: &str) {
+    log_from_caller(Level::Warn, message);
+}
+
+#[track_caller]
 pub fn error(message: &str) {
-    if let Ok(mut logger) = LOGGER.lock() {
-        if let Err(e) = logger.write_log("ERROR", message) {
-            eprintln!("Logging error: {}", e);
-        }
-    }
+    log_from_caller(Level::Error, message);
 }
 
+#[track_caller]
 pub fn debug(message: &str) {
-    if let Ok(mut logger) = LOGGER.lock() {
-        if let Err(e) = logger.write_log("DEBUG", message) {
-            eprintln!("Logging error: {}", e);
+    log_from_caller(Level::Debug, message);
+}
+
+// Write out buffered lines, e.g. before reading the log back
+pub fn flush() {
+    log::logger().flush();
+}
+
+// Directory holding the current and rotated logs
+pub fn log_dir() -> PathBuf {
+    SINK.lock().map(|sink| sink.dir.clone()).unwrap_or_else(|_| PathBuf::from("logs"))
+}
+
+// The current log and the rotated ones, newest first
+pub fn log_files() -> Vec<PathBuf> {
+    let (dir, current) = match SINK.lock() {
+        Ok(sink) => (sink.dir.clone(), sink.current_path()),
+        Err(_) => return Vec::new(),
+    };
+    let mut files: Vec<(std::time::SystemTime, PathBuf)> = rotated_files(&dir)
+        .into_iter()
+        .map(|path| (fs::metadata(&path).and_then(|m| m.modified()).unwrap_or(std::time::UNIX_EPOCH), path))
+        .collect();
+    files.sort_by_key(|file| std::cmp::Reverse(file.0));
+    let mut paths: Vec<PathBuf> = files.into_iter().map(|(_, path)| path).collect();
+    if current.exists() {
+        paths.insert(0, current);
+    }
+    paths
+}
+
+// Apply the [logging] section, at startup and on every configuration reload
+pub fn configure(settings: &LoggingConfig) {
+    if let Ok(mut sink) = SINK.lock() {
+        let reopen = resolve_dir(settings) != sink.dir || settings.format != sink.settings.format;
+        sink.settings = settings.clone();
+        sink.apply_settings();
+        if reopen {
+            sink.flush();
+            sink.dir = resolve_dir(settings);
+            sink.open();
         }
+        log::set_max_level(sink.max_level());
     }
 }
 
 // Initialize the logger
 pub fn init() -> Result<(), String> {
+    log::set_logger(&FACADE).map_err(|e| format!("Failed to install logger: {}", e))?;
+    log::set_max_level(SINK.lock().map(|sink| sink.max_level()).unwrap_or(LevelFilter::Info));
+
+    // Write out lines still buffered when nothing else has been logged since
+    std::thread::spawn(|| loop {
+        std::thread::sleep(FLUSH_INTERVAL);
+        if let Ok(mut sink) = SINK.lock() {
+            if sink.last_flush.elapsed() >= FLUSH_INTERVAL {
+                sink.flush();
+            }
+        }
+    });
+
     info("Logger initialized");
     Ok(())
 }
@@ -0,0 +1,217 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use chrono::Local;
+use regex::Regex;
+use rusqlite::{params, Connection};
+use serde::{Serialize, Deserialize};
+use crate::activity::{self, ActivityType};
+use crate::access;
+use crate::config::{self, NukeLocalizeMode};
+use crate::db;
+use crate::logger;
+use crate::paths;
+
+// Knobs holding file paths, on Read/Write/ReadGeo/Camera nodes and the Root
+const FILE_KNOBS: &[&str] = &["file", "proxy", "vfield_file", "project_directory", "customOCIOConfigPath"];
+
+// Result of remapping the paths in one script
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LocalizedScript {
+    pub source: String,
+    // The script to open: the source itself when nothing needed remapping
+    pub output: String,
+    pub backup: Option<String>,
+    pub remapped: usize,
+}
+
+// Local form of a knob value, if it is a path under one of the mappings. Nuke takes
+// forward slashes on every OS, so they are kept even on Windows.
+fn localize_value(value: &str) -> Option<String> {
+    // TCL expressions are evaluated by Nuke and left alone
+    if value.contains('[') {
+        return None;
+    }
+    paths::match_mapping(value)?;
+    let local = paths::normalize_path(value).replace('\\', "/");
+    if local == value { None } else { Some(local) }
+}
+
+// Remap one knob line, keeping the quoting style of the value
+fn localize_line(line: &str, knob_regex: &Regex) -> Option<String> {
+    let caps = knob_regex.captures(line)?;
+    let head = caps.get(1)?.as_str();
+    let value = caps.get(2)?.as_str().trim_end();
+
+    if let Some(inner) = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+        let local = localize_value(&inner.replace("\\\\", "\\"))?;
+        Some(format!("{}\"{}\"", head, local.replace('"', "\\\"")))
+    } else if let Some(inner) = value.strip_prefix('{').and_then(|v| v.strip_suffix('}')) {
+        let local = localize_value(inner.trim())?;
+        Some(format!("{}{{{}}}", head, local))
+    } else {
+        let local = localize_value(value)?;
+        if local.contains(' ') {
+            Some(format!("{}\"{}\"", head, local))
+        } else {
+            Some(format!("{}{}", head, local))
+        }
+    }
+}
+
+// Rewrite every file knob in a script's text to this machine's paths.
+// Returns the new text and how many knobs changed.
+pub fn localize_script(contents: &str) -> (String, usize) {
+    let knob_regex = Regex::new(&format!(r"^(\s*(?:{})\s+)(\S.*)$", FILE_KNOBS.join("|"))).unwrap();
+    let mut remapped = 0;
+    let lines: Vec<String> = contents
+        .split('\n')
+        .map(|line| {
+            let (line, cr) = match line.strip_suffix('\r') {
+                Some(line) => (line, "\r"),
+                None => (line, ""),
+            };
+            match localize_line(line, &knob_regex) {
+                Some(local) => {
+                    remapped += 1;
+                    format!("{}{}", local, cr)
+                },
+                None => format!("{}{}", line, cr),
+            }
+        })
+        .collect();
+    (lines.join("\n"), remapped)
+}
+
+// Localized copies are named after the script plus a hash of its full path, so scripts
+// with the same name in different shots do not collide
+fn localized_copy_path(source: &Path) -> PathBuf {
+    let mut hasher = DefaultHasher::new();
+    source.hash(&mut hasher);
+    let stem = source.file_stem().and_then(|s| s.to_str()).unwrap_or("script");
+    let dir = paths::data_path(&config::get_config().nuke_paths.directory, "localized_scripts");
+    dir.join(format!("{}_{:08x}.nk", stem, hasher.finish() as u32))
+}
+
+// Remap a script's paths into a localized copy, or into the script itself after
+// backing it up next to it as <script>.<timestamp>.bak
+pub fn localize_file(file_path: &str, in_place: bool) -> Result<LocalizedScript, String> {
+    let source = PathBuf::from(paths::normalize_path(file_path));
+    let contents = fs::read_to_string(&source).map_err(|e| format!("Failed to read {}: {}", source.display(), e))?;
+    let (localized, remapped) = localize_script(&contents);
+
+    let mut result = LocalizedScript {
+        source: source.to_string_lossy().to_string(),
+        output: source.to_string_lossy().to_string(),
+        backup: None,
+        remapped,
+    };
+    // Paths already in this machine's form leave the script as it is; nothing to write
+    if remapped == 0 || localized == contents {
+        result.remapped = 0;
+        return Ok(result);
+    }
+
+    if in_place {
+        let backup = PathBuf::from(format!("{}.{}.bak", source.display(), Local::now().format("%Y%m%d_%H%M%S")));
+        fs::copy(&source, &backup).map_err(|e| format!("Failed to back up {}: {}", source.display(), e))?;
+        fs::write(&source, localized).map_err(|e| format!("Failed to write {}: {}", source.display(), e))?;
+        result.backup = Some(backup.to_string_lossy().to_string());
+    } else {
+        let output = localized_copy_path(&source);
+        if let Some(dir) = output.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        fs::write(&output, localized).map_err(|e| format!("Failed to write {}: {}", output.display(), e))?;
+        result.output = output.to_string_lossy().to_string();
+    }
+
+    logger::info(&format!("Remapped {} paths in {} to {}", remapped, result.source, result.output));
+    Ok(result)
+}
+
+// The script open_file should launch, after the configured [nuke_paths] on_launch step.
+// Rewriting the shared script is limited to project owners, as in localize_project_scripts;
+// everyone else launches a localized copy. A failure falls back to the original script
+// rather than blocking the launch.
+pub fn localize_for_launch(file_path: &str, project_id: Option<i64>, file_id: Option<i64>, user_id: Option<i64>) -> String {
+    let owner = |conn: &Connection| match (project_id, user_id) {
+        (Some(project_id), Some(uid)) => access::require_project_owner(conn, project_id, uid).is_ok(),
+        _ => false,
+    };
+    // Some(connection) when rewriting in place, kept to record the rewrite as activity
+    let conn = match config::get_config().nuke_paths.on_launch {
+        NukeLocalizeMode::Off => return file_path.to_string(),
+        NukeLocalizeMode::Copy => None,
+        NukeLocalizeMode::InPlace => db::get_connection().ok().filter(|conn| owner(conn)),
+    };
+    match localize_file(file_path, conn.is_some()) {
+        Ok(result) => {
+            if let (Some(conn), Some(uid), true) = (&conn, user_id, result.remapped > 0) {
+                let details = format!("Remapped {} paths on {} at launch", result.remapped, paths::get_machine_name());
+                activity::record_activity(conn, uid, ActivityType::LocalizeScript, project_id, file_id, &details);
+            }
+            result.output
+        },
+        Err(e) => {
+            logger::warn(&format!("Opening {} without remapping its paths: {}", file_path, e));
+            file_path.to_string()
+        }
+    }
+}
+
+// Remap one indexed script. In place changes the shared file, so it is recorded as activity.
+#[tauri::command]
+pub fn localize_nuke_script(file_id: i64, in_place: Option<bool>, user_id: Option<i64>) -> Result<LocalizedScript, String> {
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    let (project_id, path): (i64, String) = conn.query_row(
+        "SELECT project_id, path FROM project_files WHERE id = ?",
+        params![file_id],
+        |row| Ok((row.get(0)?, row.get(1)?))
+    ).map_err(|e| format!("File {} not found: {}", file_id, e))?;
+    access::require_open_access(&conn, project_id, user_id)?;
+
+    let in_place = in_place.unwrap_or(false);
+    let result = localize_file(&path, in_place)?;
+    if let Some(uid) = user_id.filter(|_| in_place && result.remapped > 0) {
+        let details = format!("Remapped {} paths on {}", result.remapped, paths::get_machine_name());
+        activity::record_activity(&conn, uid, ActivityType::LocalizeScript, Some(project_id), Some(file_id), &details);
+    }
+    Ok(result)
+}
+
+// Batch step over every .nk in a project. Rewriting in place is limited to project owners.
+#[tauri::command]
+pub fn localize_project_scripts(project_id: i64, in_place: Option<bool>, user_id: i64) -> Result<Vec<LocalizedScript>, String> {
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    let in_place = in_place.unwrap_or(false);
+    if in_place {
+        access::require_project_owner(&conn, project_id, user_id)?;
+    } else {
+        access::require_open_access(&conn, project_id, Some(user_id))?;
+    }
+
+    let mut stmt = conn.prepare("SELECT path FROM project_files WHERE project_id = ? AND file_type = 'nk' AND missing = 0")
+        .map_err(|e| e.to_string())?;
+    let scripts = stmt.query_map(params![project_id], |row| row.get::<_, String>(0))
+        .map_err(|e| e.to_string())?
+        .collect::<rusqlite::Result<Vec<String>>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut results = Vec::new();
+    for script in scripts {
+        match localize_file(&script, in_place) {
+            Ok(result) => results.push(result),
+            Err(e) => logger::warn(&format!("Skipping {}: {}", script, e)),
+        }
+    }
+
+    let remapped: usize = results.iter().map(|r| r.remapped).sum();
+    if in_place && remapped > 0 {
+        let details = format!("Remapped {} paths in {} scripts on {}", remapped, results.len(), paths::get_machine_name());
+        activity::record_activity(&conn, user_id, ActivityType::LocalizeScripts, Some(project_id), None, &details);
+    }
+    logger::info(&format!("Localized {} scripts in project {}", results.len(), project_id));
+    Ok(results)
+}
@@ -1,7 +1,8 @@
 use std::path::{Path, PathBuf};
 use std::env;
+use serde::{Serialize, Deserialize};
 use crate::logger;
-use crate::config;
+use crate::config::{self, DatabaseMode, PathMapping};
 
 // Enum to represent the current operating system
 #[derive(Debug, PartialEq)]
@@ -39,34 +40,149 @@ pub fn get_os_type() -> OsType {
     OsType::Unknown
 }
 
-// Convert a path to the platform-specific format
+impl PathMapping {
+    // The root of this mapping on the given OS
+    pub fn root_for(&self, os: &OsType) -> Option<&str> {
+        let root = match os {
+            OsType::Windows => &self.windows,
+            OsType::MacOS => &self.macos,
+            OsType::Linux => &self.linux,
+            OsType::Unknown => &None,
+        };
+        root.as_deref().filter(|r| !r.trim().is_empty())
+    }
+
+    fn roots(&self) -> impl Iterator<Item = &str> {
+        [&self.windows, &self.macos, &self.linux, &self.unc]
+            .into_iter()
+            .filter_map(|root| root.as_deref())
+            .filter(|root| !root.trim().is_empty())
+    }
+}
+
+// How a path resolves through the mapping rules, for the settings page and diagnostics
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PathResolution {
+    pub path: String,
+    pub mapping: Option<String>,
+    pub canonical: String,
+    pub local: String,
+}
+
+fn forward_slashes(path: &str) -> String {
+    path.replace('\\', "/")
+}
+
+// Drive letters and UNC shares compare case-insensitively
+fn is_windows_root(root: &str) -> bool {
+    root.starts_with("//") || root.chars().nth(1) == Some(':')
+}
+
+// Length of `root` if it prefixes `path` (both with forward slashes) at a path boundary
+fn root_match(path: &str, root: &str) -> Option<usize> {
+    let root = forward_slashes(root);
+    let root = root.trim_end_matches('/');
+    if root.is_empty() || path.len() < root.len() || !path.is_char_boundary(root.len()) {
+        return None;
+    }
+
+    let (head, rest) = path.split_at(root.len());
+    let matches = if is_windows_root(root) { head.eq_ignore_ascii_case(root) } else { head == root };
+    if matches && (rest.is_empty() || rest.starts_with('/')) {
+        Some(root.len())
+    } else {
+        None
+    }
+}
+
+// The configured [[path_mappings]] followed by the legacy [paths] network_base / windows_mapped_drive pair
+pub fn mapping_rules() -> Vec<PathMapping> {
+    let cfg = config::get_config();
+    let mut rules = cfg.path_mappings.clone();
+    let base = &cfg.paths.network_base;
+    // A UNC base is only a root as a UNC path; macOS reaches the share under /Volumes
+    let unc = Some(base.clone()).filter(|b| b.starts_with("//") || b.starts_with("\\\\"));
+    let macos = match &unc {
+        Some(unc) => unc_to_volume(&forward_slashes(unc)).unwrap_or_else(|| base.clone()),
+        None => base.clone(),
+    };
+    rules.push(PathMapping {
+        name: "network".to_string(),
+        windows: Some(cfg.paths.windows_mapped_drive.clone()),
+        macos: Some(macos),
+        linux: Some(base.clone()),
+        unc,
+    });
+    rules
+}
+
+// Stored paths are a root token plus the path below that root, e.g. "{naboo}/projects/foo"
+fn split_root_token(path: &str) -> Option<(&str, &str)> {
+    let token = path.strip_prefix('{')?;
+    let end = token.find('}')?;
+    let rest = &token[end + 1..];
+    if rest.is_empty() || rest.starts_with('/') {
+        Some((&token[..end], rest))
+    } else {
+        None
+    }
+}
+
+// The rule a path belongs to, and the rest of the path below that rule's root. Canonical
+// paths name their rule; OS paths take the first rule with a root prefixing them.
+pub fn match_mapping(path: &str) -> Option<(PathMapping, String)> {
+    let path = forward_slashes(path);
+    if let Some((name, rest)) = split_root_token(&path) {
+        let rule = mapping_rules().into_iter().find(|rule| rule.name == name);
+        if rule.is_none() {
+            logger::warn(&format!("No path mapping named {} for {}", name, path));
+        }
+        return rule.map(|rule| (rule, rest.to_string()));
+    }
+
+    for rule in mapping_rules() {
+        let longest = rule.roots().filter_map(|root| root_match(&path, root)).max();
+        if let Some(len) = longest {
+            let rest = path[len..].to_string();
+            return Some((rule, rest));
+        }
+    }
+    None
+}
+
+// Mounted volume for a UNC path on macOS: //server/share/rest -> /Volumes/share/rest
+fn unc_to_volume(path: &str) -> Option<String> {
+    let parts: Vec<&str> = path.trim_start_matches("//").splitn(3, '/').collect();
+    if !path.starts_with("//") || parts.len() < 2 {
+        return None;
+    }
+    let rest = parts.get(2).map(|r| format!("/{}", r)).unwrap_or_default();
+    Some(format!("/Volumes/{}{}", parts[1], rest))
+}
+
+// Convert a path to the form this machine uses, through the path mapping rules
 pub fn normalize_path(path: &str) -> String {
     let os = get_os_type();
-    let cfg = config::get_config();
-    let network_base = &cfg.paths.network_base;
-    let windows_drive = &cfg.paths.windows_mapped_drive;
+    
+    if let Some((rule, rest)) = match_mapping(path) {
+        match rule.root_for(&os) {
+            Some(root) => {
+                let local = format!("{}{}", forward_slashes(root).trim_end_matches('/'), rest);
+                return if os == OsType::Windows { local.replace('/', "\\") } else { local };
+            },
+            None => logger::warn(&format!("Path mapping {} has no root for {:?}: {}", rule.name, os, path)),
+        }
+    }
+    
+    // Unmapped SMB shares are mounted under /Volumes by Finder
+    if os == OsType::MacOS {
+        let path = forward_slashes(path);
+        return unc_to_volume(&path).unwrap_or(path);
+    }
     
     match os {
-        OsType::Windows => {
-            // Convert UNC path to Windows drive letter if applicable
-            if path.starts_with(network_base) {
-                let relative_path = path.strip_prefix(network_base).unwrap_or("");
-                format!("{}{}", windows_drive, relative_path.replace("/", "\\"))
-            } else {
-                // Just ensure Windows path separators
-                path.replace("/", "\\")
-            }
-        },
-        OsType::MacOS | OsType::Linux => {
-            // Convert Windows drive letter path to UNC if applicable
-            if path.starts_with(windows_drive) {
-                let relative_path = path.strip_prefix(windows_drive).unwrap_or("");
-                format!("{}{}", network_base, relative_path.replace("\\", "/"))
-            } else {
-                // Just ensure Unix path separators
-                path.replace("\\", "/")
-            }
-        },
+        OsType::Windows => path.replace('/', "\\"),
+        OsType::MacOS | OsType::Linux => forward_slashes(path),
         OsType::Unknown => {
             logger::warn(&format!("Unknown OS detected, using path as-is: {}", path));
             path.to_string()
@@ -74,34 +190,43 @@ pub fn normalize_path(path: &str) -> String {
     }
 }
 
+// Convert a path to the OS-neutral form stored in the database, e.g. U:\projects\foo and
+// /Volumes/Naboo/projects/foo both become {network}/projects/foo. Paths outside every
+// mapping (local disks) are stored as they are.
+pub fn canonical_path(path: &str) -> String {
+    match match_mapping(path) {
+        Some((rule, rest)) => format!("{{{}}}{}", rule.name, rest),
+        None => path.to_string(),
+    }
+}
+
+pub fn resolve(path: &str) -> PathResolution {
+    PathResolution {
+        path: path.to_string(),
+        mapping: match_mapping(path).map(|(rule, _)| rule.name),
+        canonical: canonical_path(path),
+        local: normalize_path(path),
+    }
+}
+
+#[tauri::command]
+pub fn get_path_mappings() -> Vec<PathMapping> {
+    mapping_rules()
+}
+
+// Show how a path maps, to check [[path_mappings]] rules
+#[tauri::command]
+pub fn resolve_path(path: String) -> PathResolution {
+    resolve(&path)
+}
+
 // Get the appropriate database path based on deployment mode
 pub fn get_network_database_path() -> PathBuf {
     // For network deployment, we'll use the network path
     let cfg = config::get_config();
     let db_path = format!("{}/vfx_launcher.db", cfg.database.network_path);
     
-    // Check if this is a UNC path that needs to be converted to a mounted path
-    if get_os_type() == OsType::MacOS && db_path.starts_with("//") {
-        // Convert UNC path to mounted volume path
-        let path_parts: Vec<&str> = db_path.trim_start_matches("//").split('/').collect();
-        if path_parts.len() >= 2 {
-            // Format as /Volumes/<server-name>/<share-name>/rest/of/path
-            let server = path_parts[0];
-            let share = path_parts[1];
-            let remaining_path = if path_parts.len() > 2 {
-                path_parts[2..].join("/")
-            } else {
-                String::new()
-            };
-            
-            let mounted_path = format!("/Volumes/{}/{}", share, remaining_path);
-            logger::info(&format!("Converted UNC path {} to mounted path: {}", db_path, mounted_path));
-            return PathBuf::from(mounted_path);
-        }
-    }
-    
-    // Fall back to the configured path
-    let path = PathBuf::from(db_path);
+    let path = PathBuf::from(normalize_path(&db_path));
     logger::info(&format!("Using network database path: {}", path.display()));
     path
 }
@@ -113,34 +238,137 @@ pub fn get_local_database_path() -> PathBuf {
     path
 }
 
+// Per-machine application data, e.g. ~/.local/share/vfx-launcher or %LOCALAPPDATA%\vfx-launcher
+pub fn data_dir() -> PathBuf {
+    dirs::data_local_dir()
+        .map(|dir| dir.join("vfx-launcher"))
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+// A configured per-machine directory: `default` under the data directory when empty, and
+// relative paths taken from the data directory rather than wherever the launcher was started
+pub fn data_path(configured: &str, default: &str) -> PathBuf {
+    let configured = configured.trim();
+    if configured.is_empty() {
+        return data_dir().join(default);
+    }
+    let path = PathBuf::from(normalize_path(configured));
+    if path.is_absolute() || configured.starts_with('{') {
+        path
+    } else {
+        data_dir().join(path)
+    }
+}
+
 // Convert a path from network to local format for application launching
 #[tauri::command]
 pub fn convert_to_local_path(path: String) -> String {
     normalize_path(&path)
 }
 
+// Name of this workstation, used to record where locks and sessions come from
+pub fn get_machine_name() -> String {
+    for var in ["COMPUTERNAME", "HOSTNAME"] {
+        if let Ok(name) = env::var(var) {
+            if !name.trim().is_empty() {
+                return name.trim().to_string();
+            }
+        }
+    }
+    
+    // Neither variable is exported on macOS by default, so ask the OS
+    if let Ok(output) = std::process::Command::new("hostname").output() {
+        let name = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if !name.is_empty() {
+            return name;
+        }
+    }
+    
+    "unknown".to_string()
+}
+
+// Filesystem types of network mounts, as named by /proc/mounts and `mount`
+const NETWORK_FILESYSTEMS: &[&str] = &["cifs", "smb", "smb3", "smbfs", "nfs", "nfs4", "afpfs", "webdav", "fuse.sshfs", "9p"];
+
+// Pick the filesystem of the longest mount point containing `path`
+fn longest_mount(path: &Path, mounts: impl Iterator<Item = (String, String)>) -> Option<String> {
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    mounts
+        .filter(|(mount, _)| path.starts_with(mount))
+        .max_by_key(|(mount, _)| mount.len())
+        .map(|(_, fs_type)| fs_type)
+}
+
+#[cfg(target_os = "linux")]
+fn mount_filesystem(path: &Path) -> Option<String> {
+    let mounts = std::fs::read_to_string("/proc/mounts").ok()?;
+    longest_mount(path, mounts.lines().filter_map(|line| {
+        let mut fields = line.split_whitespace().skip(1);
+        let mount = fields.next()?.replace("\\040", " ");
+        let fs_type = fields.next()?.to_string();
+        Some((mount, fs_type))
+    }))
+}
+
+#[cfg(target_os = "macos")]
+fn mount_filesystem(path: &Path) -> Option<String> {
+    // Lines look like "//user@server/Naboo on /Volumes/Naboo (smbfs, nodev, nosuid, mounted by user)"
+    let output = std::process::Command::new("mount").output().ok()?;
+    let text = String::from_utf8_lossy(&output.stdout).to_string();
+    longest_mount(path, text.lines().filter_map(|line| {
+        let (_, rest) = line.split_once(" on ")?;
+        let (mount, options) = rest.rsplit_once(" (")?;
+        let fs_type = options.split([',', ')']).next()?.trim().to_string();
+        Some((mount.to_string(), fs_type))
+    }))
+}
+
+#[cfg(target_os = "windows")]
+fn mount_filesystem(path: &Path) -> Option<String> {
+    // Mapped network drives are the ones listed by `net use`
+    let drive = path.to_string_lossy().get(..2)?.to_uppercase();
+    if !drive.ends_with(':') {
+        return None;
+    }
+    let output = std::process::Command::new("net").arg("use").output().ok()?;
+    let text = String::from_utf8_lossy(&output.stdout).to_uppercase();
+    if text.split_whitespace().any(|word| word == drive) {
+        Some("smb".to_string())
+    } else {
+        None
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn mount_filesystem(_path: &Path) -> Option<String> {
+    None
+}
+
+// Whether `path` lives on a network share: a UNC path, a path under one of the path
+// mappings, or a mount whose filesystem is SMB, NFS or similar
+pub fn is_network_path(path: &str) -> bool {
+    if path.starts_with("\\\\") || path.starts_with("//") || match_mapping(path).is_some() {
+        return true;
+    }
+    mount_filesystem(Path::new(path))
+        .map(|fs_type| NETWORK_FILESYSTEMS.contains(&fs_type.as_str()))
+        .unwrap_or(false)
+}
+
 // Check if a path exists and is readable
 pub fn check_path_access(path: &str) -> bool {
     Path::new(path).exists()
 }
 
-// Get DB configuration from environment, defaulting to config file setting
-pub fn get_database_mode() -> String {
-    match env::var("VFX_DB_MODE") {
-        Ok(mode) => mode,
-        Err(_) => {
-            // Default to config file setting
-            let cfg = config::get_config();
-            cfg.database.mode.clone()
-        }
-    }
+// Database mode; VFX_DB_MODE is applied as a configuration layer (see config.rs)
+pub fn get_database_mode() -> DatabaseMode {
+    config::get_config().database.mode
 }
 
 // Get the appropriate database path based on mode
 pub fn get_database_path() -> PathBuf {
-    let mode = get_database_mode();
-    match mode.as_str() {
-        "local" => get_local_database_path(),
-        _ => get_network_database_path(),
+    match get_database_mode() {
+        DatabaseMode::Local => get_local_database_path(),
+        DatabaseMode::Network => get_network_database_path(),
     }
 }
@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use notify::event::{CreateKind, DataChange, ModifyKind, RemoveKind};
+use notify::{Event, EventKind};
+use crate::logger;
+
+// What a file looked like at the last poll
+type Snapshot = HashMap<PathBuf, (SystemTime, u64)>;
+
+// Watches directories by diffing mtime/size snapshots, for network shares where inotify and
+// FSEvents never hear about changes made by other machines. Events are delivered in the same
+// form as a notify watcher's, and dropping the poller stops its thread.
+pub struct SnapshotPoller {
+    stop: Arc<AtomicBool>,
+}
+
+impl Drop for SnapshotPoller {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+// Render output is never indexed and can hold thousands of frames, so it is not polled
+fn is_render_dir(path: &Path) -> bool {
+    path.file_name()
+        .map(|name| {
+            let name = name.to_string_lossy().to_lowercase();
+            name == "render" || name == "renders"
+        })
+        .unwrap_or(false)
+}
+
+fn snapshot_dir(dir: &Path, snapshot: &mut Snapshot) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        // Entries vanishing mid-walk show up as removed on the next poll; any other error fails
+        // the snapshot, so an unreadable folder is not taken for removed files
+        let metadata = match entry.metadata() {
+            Ok(metadata) => metadata,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        if metadata.is_dir() {
+            if !is_render_dir(&path) {
+                match snapshot_dir(&path, snapshot) {
+                    Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+                    _ => (),
+                }
+            }
+        } else {
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            snapshot.insert(path, (modified, metadata.len()));
+        }
+    }
+    Ok(())
+}
+
+fn take_snapshot(roots: &[PathBuf]) -> std::io::Result<Snapshot> {
+    let mut snapshot = Snapshot::new();
+    for root in roots {
+        snapshot_dir(root, &mut snapshot)?;
+    }
+    Ok(snapshot)
+}
+
+fn diff(previous: &Snapshot, current: &Snapshot) -> Vec<Event> {
+    let mut events = Vec::new();
+    for (path, state) in current {
+        match previous.get(path) {
+            None => events.push(Event::new(EventKind::Create(CreateKind::File)).add_path(path.clone())),
+            Some(old) if old != state => events.push(Event::new(EventKind::Modify(ModifyKind::Data(DataChange::Any))).add_path(path.clone())),
+            Some(_) => (),
+        }
+    }
+    for path in previous.keys().filter(|path| !current.contains_key(*path)) {
+        events.push(Event::new(EventKind::Remove(RemoveKind::File)).add_path(path.clone()));
+    }
+    events
+}
+
+impl SnapshotPoller {
+    pub fn start(roots: Vec<PathBuf>, interval: Duration, tx: Sender<notify::Result<Event>>) -> Result<SnapshotPoller, String> {
+        let mut previous = take_snapshot(&roots).map_err(|e| e.to_string())?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+
+        std::thread::spawn(move || {
+            // Sleep in short steps so a stopped poller lets go of the channel promptly
+            let step = Duration::from_millis(250).min(interval);
+            'polling: loop {
+                let mut waited = Duration::ZERO;
+                while waited < interval {
+                    if thread_stop.load(Ordering::Relaxed) {
+                        break 'polling;
+                    }
+                    std::thread::sleep(step);
+                    waited += step;
+                }
+
+                match take_snapshot(&roots) {
+                    Ok(current) => {
+                        for event in diff(&previous, &current) {
+                            if tx.send(Ok(event)).is_err() {
+                                break 'polling;
+                            }
+                        }
+                        previous = current;
+                    },
+                    // An unreachable share is reported once per poll rather than read as every file removed
+                    Err(e) => {
+                        logger::warn(&format!("Polling {:?} failed: {}", roots, e));
+                        if tx.send(Err(notify::Error::io(e))).is_err() {
+                            break 'polling;
+                        }
+                    },
+                }
+            }
+        });
+
+        Ok(SnapshotPoller { stop })
+    }
+}
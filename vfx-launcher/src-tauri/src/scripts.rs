@@ -0,0 +1,333 @@
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use serde::{Serialize, Deserialize};
+use rusqlite::{params, Connection, OptionalExtension};
+use chrono::Utc;
+use regex::Regex;
+use crate::activity::{self, ActivityType};
+use crate::access;
+use crate::db::{self, ProjectFile};
+use crate::environment;
+use crate::events;
+use crate::files::{self, FileChanges};
+use crate::logger;
+use crate::paths;
+use crate::versions;
+
+// Where new scripts and their renders go, relative to the project root
+const DEFAULT_SCRIPT_PATTERN: &str = "shots/{shot}/{task}/{shot}_{task}_v{version}.{ext}";
+const DEFAULT_RENDER_PATTERN: &str = "shots/{shot}/renders/{shot}_{task}_v{version}/{shot}_{task}_v{version}.####.exr";
+
+// The file a new shot script starts from. Templates without a project are studio defaults.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ScriptTemplate {
+    pub id: i64,
+    pub project_id: Option<i64>,
+    // "nk" or "aep"
+    pub file_type: String,
+    pub template_path: String,
+    // Location of new scripts, e.g. "shots/{shot}/{task}/{shot}_{task}_v{version}.{ext}"
+    pub script_pattern: String,
+    // Write node output, available in the template as {{write_path}}
+    pub render_pattern: Option<String>,
+}
+
+fn map_template(row: &rusqlite::Row) -> rusqlite::Result<ScriptTemplate> {
+    Ok(ScriptTemplate {
+        id: row.get(0)?,
+        project_id: row.get(1)?,
+        file_type: row.get(2)?,
+        template_path: paths::normalize_path(&row.get::<_, String>(3)?),
+        script_pattern: row.get(4)?,
+        render_pattern: row.get(5)?,
+    })
+}
+
+// The project's own template for a file type, falling back to the studio default
+fn find_template(conn: &Connection, project_id: i64, file_type: &str) -> Result<Option<ScriptTemplate>, String> {
+    conn.query_row(
+        "SELECT id, project_id, file_type, template_path, script_pattern, render_pattern FROM script_templates
+         WHERE file_type = ? AND (project_id = ? OR project_id IS NULL)
+         ORDER BY project_id IS NULL ASC LIMIT 1",
+        params![file_type, project_id],
+        map_template
+    ).optional().map_err(|e| e.to_string())
+}
+
+// Fill `{token}`s in a path pattern; every token used must be known
+fn fill_pattern(pattern: &str, tokens: &HashMap<String, String>) -> Result<String, String> {
+    let token_regex = Regex::new(r"\{([a-z_]+)\}").unwrap();
+    if let Some(missing) = token_regex.captures_iter(pattern).find(|caps| !tokens.contains_key(&caps[1])) {
+        return Err(format!("{{{}}} is not available for this shot (pattern {})", &missing[1], pattern));
+    }
+    Ok(token_regex.replace_all(pattern, |caps: &regex::Captures| tokens[&caps[1]].clone()).to_string())
+}
+
+// Fill `{{token}}`s in a template script. Double braces keep clear of Nuke's own TCL braces,
+// and unknown tokens are left alone so a template can be reused before every setting exists.
+fn fill_template(content: &str, tokens: &HashMap<String, String>) -> String {
+    let token_regex = Regex::new(r"\{\{([a-z_]+)\}\}").unwrap();
+    token_regex.replace_all(content, |caps: &regex::Captures| {
+        tokens.get(&caps[1]).cloned().unwrap_or_else(|| caps[0].to_string())
+    }).to_string()
+}
+
+// Set a knob on the Root node of a Nuke script, adding it when the template does not have it
+fn set_root_knob(script: &str, knob: &str, value: &str) -> String {
+    let mut lines: Vec<String> = script.lines().map(|l| l.to_string()).collect();
+    let start = match lines.iter().position(|l| l.trim_start().starts_with("Root {")) {
+        Some(start) => start,
+        None => return script.to_string(),
+    };
+    let end = match lines.iter().skip(start).position(|l| l.trim() == "}") {
+        Some(offset) => start + offset,
+        None => return script.to_string(),
+    };
+
+    let knob_line = format!(" {} {}", knob, value);
+    let prefix = format!("{} ", knob);
+    match lines[start + 1..end].iter().position(|l| l.trim_start().starts_with(&prefix)) {
+        Some(offset) => lines[start + 1 + offset] = knob_line,
+        None => lines.insert(end, knob_line),
+    }
+
+    let mut result = lines.join("\n");
+    if script.ends_with('\n') {
+        result.push('\n');
+    }
+    result
+}
+
+// Quote a value for a Nuke knob
+fn nuke_quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "/").replace('"', "\\\""))
+}
+
+#[tauri::command]
+pub fn get_script_templates(project_id: i64, user_id: Option<i64>) -> Result<Vec<ScriptTemplate>, String> {
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    access::require_project_access(&conn, project_id, user_id)?;
+
+    let mut stmt = conn.prepare(
+        "SELECT id, project_id, file_type, template_path, script_pattern, render_pattern FROM script_templates
+         WHERE project_id = ? OR project_id IS NULL
+         ORDER BY project_id IS NULL ASC, file_type ASC"
+    ).map_err(|e| e.to_string())?;
+    let templates = stmt.query_map(params![project_id], map_template)
+        .map_err(|e| e.to_string())?
+        .collect::<rusqlite::Result<Vec<ScriptTemplate>>>()
+        .map_err(|e| e.to_string())?;
+    Ok(templates)
+}
+
+// Set the template for a project and file type. Studio defaults are admin-only.
+#[tauri::command]
+pub fn save_script_template(template: ScriptTemplate, acting_user_id: i64) -> Result<i64, String> {
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    match template.project_id {
+        Some(project_id) => access::require_project_owner(&conn, project_id, acting_user_id)?,
+        None if !access::is_admin(&conn, acting_user_id) => {
+            return Err("Only admins can change the studio script templates".to_string());
+        },
+        None => (),
+    }
+    if template.template_path.trim().is_empty() {
+        return Err("Template path is required".to_string());
+    }
+
+    let file_type = template.file_type.trim().trim_start_matches('.').to_lowercase();
+    let template_path = paths::canonical_path(template.template_path.trim());
+    let script_pattern = Some(template.script_pattern.trim())
+        .filter(|p| !p.is_empty())
+        .unwrap_or(DEFAULT_SCRIPT_PATTERN)
+        .to_string();
+    let now = Utc::now().to_rfc3339();
+
+    // One template per project and file type, so saving replaces the existing one
+    let existing: Option<i64> = conn.query_row(
+        "SELECT id FROM script_templates WHERE file_type = ? AND project_id IS ?",
+        params![file_type, template.project_id],
+        |row| row.get(0)
+    ).optional().map_err(|e| e.to_string())?;
+    let id = match existing {
+        Some(id) => {
+            conn.execute(
+                "UPDATE script_templates SET template_path = ?, script_pattern = ?, render_pattern = ?, updated_at = ? WHERE id = ?",
+                params![template_path, script_pattern, template.render_pattern, now, id],
+            ).map_err(|e| e.to_string())?;
+            id
+        },
+        None => {
+            conn.execute(
+                "INSERT INTO script_templates (project_id, file_type, template_path, script_pattern, render_pattern, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?)",
+                params![template.project_id, file_type, template_path, script_pattern, template.render_pattern, now, now],
+            ).map_err(|e| e.to_string())?;
+            conn.last_insert_rowid()
+        },
+    };
+
+    logger::info(&format!("Saved .{} script template {} for project {:?}", file_type, template.template_path, template.project_id));
+    Ok(id)
+}
+
+#[tauri::command]
+pub fn delete_script_template(id: i64, acting_user_id: i64) -> Result<bool, String> {
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    let project_id: Option<Option<i64>> = conn.query_row(
+        "SELECT project_id FROM script_templates WHERE id = ?",
+        params![id],
+        |row| row.get(0)
+    ).optional().map_err(|e| e.to_string())?;
+
+    match project_id {
+        None => return Ok(false),
+        Some(Some(project_id)) => access::require_project_owner(&conn, project_id, acting_user_id)?,
+        Some(None) if !access::is_admin(&conn, acting_user_id) => {
+            return Err("Only admins can change the studio script templates".to_string());
+        },
+        Some(None) => (),
+    }
+
+    conn.execute("DELETE FROM script_templates WHERE id = ?", params![id])
+        .map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+// Create v001 of a shot's script for a task from the project's template, index it and
+// optionally open it. Nuke templates get the shot's frame range, format, plates and
+// render path; After Effects projects are binary and are copied as they are.
+#[tauri::command]
+pub fn new_shot_script(
+    project_id: i64,
+    shot_name: String,
+    task: String,
+    file_type: Option<String>,
+    user_id: Option<i64>,
+    launch: Option<bool>
+) -> Result<ProjectFile, String> {
+    let shot_name = shot_name.trim().to_string();
+    let task = task.trim().to_string();
+    if shot_name.is_empty() || task.is_empty() {
+        return Err("Shot and task are required".to_string());
+    }
+    // Both end up in the script's path, so they may not step out of the project
+    for (label, value) in [("Shot", &shot_name), ("Task", &task)] {
+        if value.contains('/') || value.contains('\\') || value.contains("..") {
+            return Err(format!("{} may not contain '/', '\\' or '..': {}", label, value));
+        }
+    }
+    let file_type = file_type.unwrap_or_else(|| "nk".to_string()).trim_start_matches('.').to_lowercase();
+
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    access::require_open_access(&conn, project_id, user_id)?;
+
+    let (project_name, project_path): (String, String) = conn.query_row(
+        "SELECT name, path FROM projects WHERE id = ?",
+        params![project_id],
+        |row| Ok((row.get(0)?, row.get(1)?))
+    ).map_err(|e| format!("Project {} not found: {}", project_id, e))?;
+    let project_root = PathBuf::from(paths::normalize_path(&project_path));
+
+    let template = find_template(&conn, project_id, &file_type)?
+        .ok_or_else(|| format!("No .{} script template is configured for {}", file_type, project_name))?;
+    let template_path = paths::normalize_path(&template.template_path);
+
+    let mut tokens: HashMap<String, String> = HashMap::new();
+    tokens.insert("project".to_string(), project_name.clone());
+    tokens.insert("project_path".to_string(), project_root.to_string_lossy().to_string());
+    tokens.insert("shot".to_string(), shot_name.clone());
+    tokens.insert("task".to_string(), task.clone());
+    tokens.insert("version".to_string(), "001".to_string());
+    tokens.insert("ext".to_string(), file_type.clone());
+    if let Some(seq) = environment::sequence_of(&shot_name) {
+        tokens.insert("seq".to_string(), seq.to_string());
+    }
+    let shot = environment::get_shot(&conn, project_id, &shot_name)?;
+    if let Some(shot) = &shot {
+        // Same token names as launch argument templates: {first}, {last}, {format}, {plate_path}
+        for (name, value) in environment::shot_variables(shot) {
+            let token = match name.as_str() {
+                "FIRST_FRAME" => "first".to_string(),
+                "LAST_FRAME" => "last".to_string(),
+                other => other.to_lowercase(),
+            };
+            tokens.insert(token, value);
+        }
+    }
+
+    let relative_path = fill_pattern(&template.script_pattern, &tokens)?;
+    let script_path = project_root.join(&relative_path);
+    tokens.insert("script_path".to_string(), script_path.to_string_lossy().to_string());
+    let render_pattern = template.render_pattern.as_deref().filter(|p| !p.is_empty()).unwrap_or(DEFAULT_RENDER_PATTERN);
+    let write_path = project_root.join(fill_pattern(render_pattern, &tokens)?);
+    tokens.insert("write_path".to_string(), write_path.to_string_lossy().replace('\\', "/"));
+
+    let contents = fs::read(&template_path).map_err(|e| format!("Failed to read template {}: {}", template_path, e))?;
+    let contents = if file_type == "nk" {
+        let mut script = fill_template(&String::from_utf8_lossy(&contents), &tokens);
+        script = set_root_knob(&script, "name", &nuke_quote(&script_path.to_string_lossy()));
+        if let Some(shot) = &shot {
+            if let Some(first) = shot.first_frame {
+                script = set_root_knob(&script, "first_frame", &first.to_string());
+            }
+            if let Some(last) = shot.last_frame {
+                script = set_root_knob(&script, "last_frame", &last.to_string());
+            }
+            if let Some(format) = shot.format.as_deref().filter(|f| !f.is_empty()) {
+                script = set_root_knob(&script, "format", &nuke_quote(format));
+            }
+        }
+        script.into_bytes()
+    } else {
+        contents
+    };
+
+    if let Some(parent) = script_path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&script_path)
+        .map_err(|e| format!("Failed to create {}: {}", script_path.display(), e))?;
+    file.write_all(&contents).map_err(|e| format!("Failed to write {}: {}", script_path.display(), e))?;
+    drop(file);
+
+    let stem = script_path.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+    let filename = versions::split_version(stem)
+        .map(|(prefix, _)| prefix.trim_end_matches('_').to_string())
+        .unwrap_or_else(|| stem.to_string());
+    let relative = Path::new(&relative_path);
+    let mut new_file = ProjectFile {
+        id: 0,
+        project_id,
+        filename,
+        version: "001".to_string(),
+        file_type: file_type.clone(),
+        path: script_path.to_string_lossy().to_string(),
+        relative_path: relative_path.clone(),
+        parent_folder: relative.parent().map(|p| p.to_string_lossy().to_string()).unwrap_or_default(),
+        shot_name: Some(shot_name.clone()),
+        last_modified: Utc::now().naive_utc().to_string(),
+        created_at: Utc::now().to_string(),
+        missing: false,
+    };
+    new_file.id = db::insert_project_file(&conn, &new_file)?;
+
+    let details = format!("Created {} for {} {} from {}", relative_path, shot_name, task, template.template_path);
+    if let Some(uid) = user_id {
+        activity::record_activity(&conn, uid, ActivityType::NewScript, Some(project_id), Some(new_file.id), &details);
+    }
+    logger::info(&details);
+    drop(conn);
+    events::publish_file_changes(new_file.project_id, &FileChanges { added: vec![new_file.id], ..Default::default() });
+
+    if launch.unwrap_or(false) {
+        files::open_file(new_file.path.clone(), None, None, user_id, Some(new_file.id), None)
+            .map_err(|e| format!("Created {} but failed to open it: {}", relative_path, e))?;
+    }
+
+    Ok(new_file)
+}
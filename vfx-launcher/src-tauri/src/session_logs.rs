@@ -0,0 +1,111 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, SystemTime};
+use chrono::Local;
+use crate::config;
+use crate::environment::ResolvedVariable;
+use crate::logger;
+use crate::paths;
+use crate::sessions;
+
+// Output of one launch, shared by the child's stdout and stderr. The child writes to the
+// file directly rather than through a pipe, so it keeps logging after the launcher quits.
+pub struct SessionLog {
+    pub path: PathBuf,
+    file: File,
+}
+
+impl SessionLog {
+    // A handle to give the child as stdout or stderr
+    pub fn stdio(&self) -> Result<Stdio, String> {
+        self.file.try_clone().map(Stdio::from).map_err(|e| e.to_string())
+    }
+}
+
+fn log_directory() -> PathBuf {
+    paths::data_path(&config::get_config().session_logs.directory, "logs/sessions")
+}
+
+// Drop the oldest logs beyond the configured count and any older than the configured age
+fn rotate(dir: &Path) {
+    let cfg = &config::get_config().session_logs;
+    let mut logs: Vec<(PathBuf, SystemTime)> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().map(|ext| ext == "log").unwrap_or(false))
+            .filter_map(|path| {
+                let modified = fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+                Some((path, modified))
+            })
+            .collect(),
+        Err(_) => return,
+    };
+    logs.sort_by_key(|log| std::cmp::Reverse(log.1));
+
+    let max_age = Duration::from_secs(cfg.max_age_days.max(1) * 24 * 60 * 60);
+    let now = SystemTime::now();
+    for (index, (path, modified)) in logs.iter().enumerate() {
+        let too_old = now.duration_since(*modified).map(|age| age > max_age).unwrap_or(false);
+        if index >= cfg.keep_sessions || too_old {
+            if let Err(e) = fs::remove_file(path) {
+                logger::warn(&format!("Failed to remove old session log {}: {}", path.display(), e));
+            }
+        }
+    }
+}
+
+// Create the log for a launch and write the command line and injected environment as a header
+pub fn create_session_log(command: &Command, environment: &[ResolvedVariable]) -> Result<SessionLog, String> {
+    let dir = log_directory();
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create session log directory {}: {}", dir.display(), e))?;
+    rotate(&dir);
+
+    let now = Local::now();
+    let path = dir.join(format!("session_{}.log", now.format("%Y%m%d_%H%M%S_%3f")));
+
+    let mut header = String::new();
+    header.push_str(&format!("# Launched {} on {}\n", now.format("%Y-%m-%d %H:%M:%S"), paths::get_machine_name()));
+    header.push_str(&format!("# Command: {:?}\n", command));
+    header.push_str("# Environment:\n");
+    for var in environment {
+        header.push_str(&format!("#   {}={} ({})\n", var.name, var.value, var.source));
+    }
+    header.push_str("#\n");
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to create session log {}: {}", path.display(), e))?;
+    file.write_all(header.as_bytes()).map_err(|e| e.to_string())?;
+
+    Ok(SessionLog { path, file })
+}
+
+// Contents of a session's log, optionally only the last `tail_lines` lines
+#[tauri::command]
+pub fn get_session_log(session_id: i64, tail_lines: Option<usize>, user_id: Option<i64>) -> Result<String, String> {
+    let session = sessions::get_session(session_id, user_id)?;
+    let log_path = session.log_path
+        .ok_or_else(|| format!("Session {} has no log", session_id))?;
+
+    let contents = fs::read(&log_path).map_err(|e| {
+        if session.machine != paths::get_machine_name() {
+            format!("The log for session {} is on {} ({}): {}", session_id, session.machine, log_path, e)
+        } else {
+            format!("Failed to read session log {}: {}", log_path, e)
+        }
+    })?;
+    let contents = String::from_utf8_lossy(&contents).to_string();
+
+    match tail_lines {
+        Some(count) => {
+            let lines: Vec<&str> = contents.lines().collect();
+            Ok(lines[lines.len().saturating_sub(count)..].join("\n"))
+        },
+        None => Ok(contents),
+    }
+}
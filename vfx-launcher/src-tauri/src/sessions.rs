@@ -0,0 +1,308 @@
+use std::collections::HashMap;
+use std::process::Child;
+use std::sync::{Arc, Mutex, Once};
+use std::time::{Duration, Instant};
+use serde::{Serialize, Deserialize};
+use rusqlite::{params, Connection, OptionalExtension};
+use chrono::Utc;
+use crate::activity::{self, ActivityType};
+use crate::access;
+use crate::db;
+use crate::locks;
+use crate::logger;
+use crate::paths;
+
+// Children launched by this process, keyed by launch_sessions id
+lazy_static::lazy_static! {
+    static ref SESSIONS: Arc<Mutex<HashMap<i64, RunningSession>>> = Arc::new(Mutex::new(HashMap::new()));
+}
+
+static MONITOR: Once = Once::new();
+
+// How often the monitor thread polls running children for exit
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+// How often a session refreshes the lock it holds, well inside the shortest lock expiry
+const LOCK_HEARTBEAT: Duration = Duration::from_secs(30);
+
+struct RunningSession {
+    child: Child,
+    user_id: Option<i64>,
+    project_id: Option<i64>,
+    file_id: Option<i64>,
+    application: String,
+    started: Instant,
+    // The file lock this session took (file id, user id), and when it was last refreshed
+    lock: Option<(i64, i64)>,
+    lock_heartbeat: Instant,
+}
+
+// A DCC launch, as recorded in the launch_sessions table
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LaunchSession {
+    pub id: i64,
+    pub user_id: Option<i64>,
+    pub username: Option<String>,
+    pub project_id: Option<i64>,
+    pub file_id: Option<i64>,
+    pub file_path: String,
+    pub application: String,
+    pub machine: String,
+    pub pid: Option<i64>,
+    // "running", "exited" (code 0), "crashed" (non-zero code or signal) or "lost" (launcher quit first)
+    pub status: String,
+    pub exit_code: Option<i64>,
+    pub started_at: String,
+    pub ended_at: Option<String>,
+    pub duration_seconds: Option<i64>,
+    // Captured stdout/stderr of the application
+    pub log_path: Option<String>,
+}
+
+// What open_file knows about a launch it is handing over to the tracker
+pub struct NewSession<'a> {
+    pub user_id: Option<i64>,
+    pub project_id: Option<i64>,
+    pub file_id: Option<i64>,
+    pub file_path: &'a str,
+    pub application: &'a str,
+    pub log_path: Option<&'a str>,
+    // Set when open_file checked the file out for this launch
+    pub holds_lock: bool,
+}
+
+const SESSION_COLUMNS: &str =
+    "s.id, s.user_id, u.username, s.project_id, s.file_id, s.file_path, s.application, s.machine, s.pid,
+     s.status, s.exit_code, s.started_at, s.ended_at, s.duration_seconds, s.log_path";
+
+fn map_session(row: &rusqlite::Row) -> rusqlite::Result<LaunchSession> {
+    Ok(LaunchSession {
+        id: row.get(0)?,
+        user_id: row.get(1)?,
+        username: row.get(2)?,
+        project_id: row.get(3)?,
+        file_id: row.get(4)?,
+        file_path: row.get(5)?,
+        application: row.get(6)?,
+        machine: row.get(7)?,
+        pid: row.get(8)?,
+        status: row.get(9)?,
+        exit_code: row.get(10)?,
+        started_at: row.get(11)?,
+        ended_at: row.get(12)?,
+        duration_seconds: row.get(13)?,
+        log_path: row.get(14)?,
+    })
+}
+
+// Record a freshly spawned child and start tracking it until it exits
+pub fn register_session(child: Child, session: NewSession) -> Result<i64, String> {
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    let now = Utc::now().to_rfc3339();
+    conn.execute(
+        "INSERT INTO launch_sessions (user_id, project_id, file_id, file_path, application, machine, pid, status, started_at, log_path)
+         VALUES (?, ?, ?, ?, ?, ?, ?, 'running', ?, ?)",
+        params![
+            session.user_id,
+            session.project_id,
+            session.file_id,
+            session.file_path,
+            session.application,
+            paths::get_machine_name(),
+            child.id() as i64,
+            now,
+            session.log_path
+        ],
+    ).map_err(|e| e.to_string())?;
+    let session_id = conn.last_insert_rowid();
+
+    logger::info(&format!("Session {} started: {} (pid {}) for {}", session_id, session.application, child.id(), session.file_path));
+
+    SESSIONS.lock().map_err(|e| e.to_string())?.insert(session_id, RunningSession {
+        child,
+        user_id: session.user_id,
+        project_id: session.project_id,
+        file_id: session.file_id,
+        application: session.application.to_string(),
+        started: Instant::now(),
+        lock: session.file_id.zip(session.user_id).filter(|_| session.holds_lock),
+        lock_heartbeat: Instant::now(),
+    });
+    MONITOR.call_once(|| {
+        std::thread::spawn(monitor_sessions);
+    });
+
+    Ok(session_id)
+}
+
+fn monitor_sessions() {
+    loop {
+        std::thread::sleep(POLL_INTERVAL);
+
+        // Collect finished children and due lock heartbeats under the lock, record them after
+        // releasing it
+        let mut finished = Vec::new();
+        let mut heartbeats = Vec::new();
+        if let Ok(mut sessions) = SESSIONS.lock() {
+            let mut done = Vec::new();
+            for (id, session) in sessions.iter_mut() {
+                match session.child.try_wait() {
+                    Ok(Some(status)) => done.push((*id, Some(status.code()))),
+                    Ok(None) => {
+                        if let Some(lock) = session.lock.filter(|_| session.lock_heartbeat.elapsed() >= LOCK_HEARTBEAT) {
+                            session.lock_heartbeat = Instant::now();
+                            heartbeats.push((*id, lock));
+                        }
+                    },
+                    Err(e) => {
+                        logger::warn(&format!("Lost track of session {}: {}", id, e));
+                        done.push((*id, None));
+                    },
+                }
+            }
+            for (id, code) in done {
+                if let Some(session) = sessions.remove(&id) {
+                    finished.push((id, session, code));
+                }
+            }
+        }
+
+        for (id, (file_id, user_id)) in heartbeats {
+            let alive = db::get_connection().map_err(|e| e.to_string())
+                .and_then(|conn| locks::heartbeat(&conn, file_id, user_id));
+            match alive {
+                Ok(true) => (),
+                // Expired or force-released; the application keeps running without it
+                Ok(false) => {
+                    logger::warn(&format!("Session {} lost its lock on file {}", id, file_id));
+                    if let Ok(mut sessions) = SESSIONS.lock() {
+                        if let Some(session) = sessions.get_mut(&id) {
+                            session.lock = None;
+                        }
+                    }
+                },
+                Err(e) => logger::warn(&format!("Failed to refresh the lock of session {}: {}", id, e)),
+            }
+        }
+
+        for (id, session, code) in finished {
+            if let Err(e) = finish_session(id, &session, code) {
+                logger::error(&format!("Failed to record end of session {}: {}", id, e));
+            }
+        }
+    }
+}
+
+// `code` is None when waiting failed, Some(None) when the process was killed by a signal
+fn finish_session(session_id: i64, session: &RunningSession, code: Option<Option<i32>>) -> Result<(), String> {
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    let duration = session.started.elapsed().as_secs() as i64;
+    let exit_code = code.flatten();
+    let status = match code {
+        Some(Some(0)) => "exited",
+        Some(_) => "crashed",
+        None => "lost",
+    };
+
+    conn.execute(
+        "UPDATE launch_sessions SET status = ?, exit_code = ?, ended_at = ?, duration_seconds = ? WHERE id = ?",
+        params![status, exit_code, Utc::now().to_rfc3339(), duration, session_id],
+    ).map_err(|e| e.to_string())?;
+
+    let details = match exit_code {
+        Some(code) => format!("{} {} with code {} after {}s", session.application, status, code, duration),
+        None => format!("{} {} after {}s", session.application, status, duration),
+    };
+    if let Some(user_id) = session.user_id {
+        activity::record_activity(&conn, user_id, ActivityType::SessionEnd, session.project_id, session.file_id, &details);
+    }
+    logger::info(&format!("Session {} ended: {}", session_id, details));
+
+    if let Some((file_id, user_id)) = session.lock {
+        locks::release_session_lock(file_id, user_id)?;
+    }
+    Ok(())
+}
+
+// Databases created before session logs lack the launch_sessions.log_path column
+pub fn add_session_log_column(conn: &Connection) -> Result<(), String> {
+    let has_column: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM pragma_table_info('launch_sessions') WHERE name = 'log_path')",
+        [],
+        |row| row.get(0)
+    ).map_err(|e| e.to_string())?;
+    if !has_column {
+        conn.execute_batch("ALTER TABLE launch_sessions ADD COLUMN log_path TEXT")
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+// Sessions this machine left "running" when the launcher last quit can no longer be tracked
+pub fn recover_stale_sessions() -> Result<(), String> {
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    let updated = conn.execute(
+        "UPDATE launch_sessions SET status = 'lost', ended_at = ? WHERE status = 'running' AND machine = ?",
+        params![Utc::now().to_rfc3339(), paths::get_machine_name()],
+    ).map_err(|e| e.to_string())?;
+    if updated > 0 {
+        logger::warn(&format!("Marked {} sessions from a previous run as lost", updated));
+    }
+    Ok(())
+}
+
+// Username of another user with a live session on the file, on any workstation
+pub fn running_session_user(conn: &Connection, file_id: i64, current_user_id: i64) -> Result<Option<String>, String> {
+    conn.query_row(
+        "SELECT u.username FROM launch_sessions s
+         JOIN users u ON s.user_id = u.id
+         WHERE s.file_id = ? AND s.user_id != ? AND s.status = 'running'
+         ORDER BY s.started_at DESC LIMIT 1",
+        params![file_id, current_user_id],
+        |row| row.get(0)
+    ).optional().map_err(|e| e.to_string())
+}
+
+// Running sessions on all workstations, optionally limited to one project. Sessions in
+// restricted projects the user cannot see are left out.
+#[tauri::command]
+pub fn get_running_sessions(project_id: Option<i64>, user_id: Option<i64>) -> Result<Vec<LaunchSession>, String> {
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    if let Some(project_id) = project_id {
+        access::require_project_access(&conn, project_id, user_id)?;
+    }
+    let is_admin = user_id.map(|uid| access::is_admin(&conn, uid)).unwrap_or(false);
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM launch_sessions s
+         LEFT JOIN users u ON s.user_id = u.id
+         WHERE s.status = 'running' AND (?1 IS NULL OR s.project_id = ?1)
+           AND (?2 OR s.project_id IS NULL OR EXISTS(SELECT 1 FROM projects p WHERE p.id = s.project_id AND {}))
+         ORDER BY s.started_at DESC",
+        SESSION_COLUMNS,
+        access::VISIBLE_PROJECT_FILTER
+    )).map_err(|e| e.to_string())?;
+    // Without a user only unrestricted projects are visible
+    let sessions = stmt.query_map(params![project_id, is_admin, user_id.unwrap_or(-1)], map_session)
+        .map_err(|e| e.to_string())?
+        .collect::<rusqlite::Result<Vec<LaunchSession>>>()
+        .map_err(|e| e.to_string())?;
+    Ok(sessions)
+}
+
+// A session, if the user may see the project it was launched in
+#[tauri::command]
+pub fn get_session(session_id: i64, user_id: Option<i64>) -> Result<LaunchSession, String> {
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    let session = conn.query_row(
+        &format!("SELECT {} FROM launch_sessions s LEFT JOIN users u ON s.user_id = u.id WHERE s.id = ?", SESSION_COLUMNS),
+        params![session_id],
+        map_session
+    ).optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Session {} not found", session_id))?;
+    if let Some(project_id) = session.project_id {
+        access::require_project_access(&conn, project_id, user_id)?;
+    }
+    Ok(session)
+}
@@ -0,0 +1,163 @@
+use std::fs::{self, OpenOptions};
+use std::path::Path;
+use chrono::Utc;
+use regex::Regex;
+use crate::activity::{self, ActivityType};
+use crate::access;
+use crate::config;
+use crate::db::{self, ProjectFile};
+use crate::events;
+use crate::files::{self, FileChanges};
+use crate::logger;
+use crate::paths;
+
+// Split a file stem into everything before the version number and the version digits,
+// e.g. "ABC_0010_comp_v003" -> ("ABC_0010_comp_", "003")
+pub fn split_version(stem: &str) -> Option<(&str, &str)> {
+    let version_regex = Regex::new(r"v(\d+)$").unwrap();
+    version_regex.captures(stem)
+        .and_then(|caps| caps.get(1))
+        .map(|digits| (&stem[..digits.start() - 1], digits.as_str()))
+}
+
+// Swap the string after the last path separator, keeping whichever separator style the path uses
+fn replace_file_name(path: &str, file_name: &str) -> String {
+    match path.rfind(['/', '\\']) {
+        Some(index) => format!("{}{}", &path[..=index], file_name),
+        None => file_name.to_string(),
+    }
+}
+
+// The [versions] initials pattern as a group to embed in other patterns
+fn initials_pattern() -> String {
+    format!("(?:{})", config::get_config().versions.initials_pattern)
+}
+
+// Artist initials are the segment right before the version when it matches the configured
+// pattern, e.g. "comp_NP_"; anything else, such as a task name, stays in the prefix
+fn split_initials(prefix: &str) -> Result<(&str, Option<&str>), String> {
+    let initials_regex = Regex::new(&format!(r"(?:^|_)({})_$", initials_pattern())).map_err(|e| e.to_string())?;
+    Ok(match initials_regex.captures(prefix).and_then(|caps| caps.get(1)) {
+        Some(initials) => (&prefix[..initials.start()], Some(initials.as_str())),
+        None => (prefix, None),
+    })
+}
+
+// Highest version of `prefix`, with or without any artist's initials, already in `dir`
+fn highest_version(dir: &Path, prefix: &str, ext: &str) -> u64 {
+    let pattern = format!(
+        r"^(?i:{})(?:{}_)?v(\d+)\.(?i:{})$",
+        regex::escape(prefix),
+        initials_pattern(),
+        regex::escape(ext)
+    );
+    let sibling_regex = match Regex::new(&pattern) {
+        Ok(re) => re,
+        Err(_) => return 0,
+    };
+
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter_map(|entry| {
+                    let name = entry.file_name().to_string_lossy().to_string();
+                    sibling_regex.captures(&name)
+                        .and_then(|caps| caps.get(1))
+                        .and_then(|digits| digits.as_str().parse::<u64>().ok())
+                })
+                .max()
+                .unwrap_or(0)
+        })
+        .unwrap_or(0)
+}
+
+// Copy a script to the next free version next to it, e.g. comp_v003.nk -> comp_v004.nk,
+// keeping the zero padding. With `initials` the artist initials in the name are replaced
+// (or added before the version). The new file is indexed and optionally launched.
+#[tauri::command]
+pub fn version_up(
+    file_id: i64,
+    user_id: Option<i64>,
+    initials: Option<String>,
+    launch: Option<bool>
+) -> Result<ProjectFile, String> {
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    let file = db::get_project_file(&conn, file_id)?;
+    access::require_open_access(&conn, file.project_id, user_id)?;
+
+    let source = paths::normalize_path(&file.path);
+    let source_path = Path::new(&source);
+    if !source_path.is_file() {
+        return Err(format!("File does not exist: {}", file.path));
+    }
+    let dir = source_path.parent().ok_or_else(|| format!("Invalid file path: {}", file.path))?;
+    let stem = source_path.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+    let ext = source_path.extension().and_then(|s| s.to_str()).unwrap_or_default();
+
+    // Unversioned files count as v001 with the usual three-digit padding
+    let unversioned_prefix = format!("{}_", stem);
+    let (prefix, digits) = split_version(stem).unwrap_or((unversioned_prefix.as_str(), "001"));
+    let width = digits.len();
+    let current: u64 = digits.parse().unwrap_or(1);
+
+    // The next number is free across every artist's initials, with or without new initials
+    let (head, _) = split_initials(prefix)?;
+    let next = current.max(highest_version(dir, head, ext)) + 1;
+    let initials = initials.map(|i| i.trim().to_string()).filter(|i| !i.is_empty());
+    // Initials the next version up would not recognize would start a separate series
+    if let Some(initials) = &initials {
+        if split_initials(&format!("{}_", initials))?.1 != Some(initials.as_str()) {
+            return Err(format!("Initials {} do not match versions.initials_pattern", initials));
+        }
+    }
+    let new_prefix = match &initials {
+        Some(initials) => format!("{}{}_", head, initials),
+        None => prefix.to_string(),
+    };
+
+    let version = format!("{:0width$}", next, width = width);
+    let new_name = format!("{}v{}.{}", new_prefix, version, ext);
+    let destination = dir.join(&new_name);
+
+    // create_new so two artists versioning up at once cannot overwrite each other
+    let mut reader = fs::File::open(source_path).map_err(|e| format!("Failed to read {}: {}", source, e))?;
+    let mut writer = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&destination)
+        .map_err(|e| format!("Failed to create {}: {}", destination.display(), e))?;
+    std::io::copy(&mut reader, &mut writer).map_err(|e| format!("Failed to copy to {}: {}", destination.display(), e))?;
+    drop(writer);
+
+    let mut new_file = ProjectFile {
+        id: 0,
+        project_id: file.project_id,
+        filename: new_prefix.trim_end_matches('_').to_string(),
+        version,
+        file_type: file.file_type.clone(),
+        path: replace_file_name(&file.path, &new_name),
+        relative_path: replace_file_name(&file.relative_path, &new_name),
+        parent_folder: file.parent_folder.clone(),
+        shot_name: file.shot_name.clone(),
+        last_modified: Utc::now().naive_utc().to_string(),
+        created_at: Utc::now().to_string(),
+        missing: false,
+    };
+    new_file.id = db::insert_project_file(&conn, &new_file)?;
+
+    let details = format!("Versioned up {} to {}", file.path, new_name);
+    if let Some(uid) = user_id {
+        activity::record_activity(&conn, uid, ActivityType::VersionUp, Some(file.project_id), Some(new_file.id), &details);
+    }
+    logger::info(&details);
+    drop(conn);
+    events::publish_file_changes(new_file.project_id, &FileChanges { added: vec![new_file.id], ..Default::default() });
+
+    if launch.unwrap_or(false) {
+        files::open_file(new_file.path.clone(), None, None, user_id, Some(new_file.id), None)
+            .map_err(|e| format!("Created {} but failed to open it: {}", new_name, e))?;
+    }
+
+    Ok(new_file)
+}
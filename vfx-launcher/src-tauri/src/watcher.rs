@@ -1,19 +1,56 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use chrono::Utc;
+use rusqlite::params;
 use serde::{Serialize, Deserialize};
-use notify::{Watcher, RecursiveMode, EventKind}; // Removed unused imports
+use notify::{Watcher, RecursiveMode, EventKind};
+use notify::event::{ModifyKind, RenameMode};
+use crate::config::{self, WatcherBackend, WatcherConfig};
+use crate::db;
+use crate::events;
 use crate::files;
+use crate::logger;
+use crate::paths;
+use crate::poller::SnapshotPoller;
 
 // Store active watchers
 lazy_static::lazy_static! {
     static ref WATCHERS: Arc<Mutex<HashMap<i64, ProjectWatcher>>> = Arc::new(Mutex::new(HashMap::new()));
 }
 
+// A burst of events longer than this many debounce windows still gets a rescan
+const MAX_DEBOUNCE_WINDOWS: u32 = 10;
+
+// Source of a project's events, only held to be dropped: that closes the event channel,
+// which ends the watch thread
+struct EventSource {
+    _native: Option<Box<dyn Watcher + Send + Sync>>,
+    _poller: Option<SnapshotPoller>,
+    // "native" or "poll"
+    backend: &'static str,
+}
+
 struct ProjectWatcher {
-    watcher: Box<dyn Watcher + Send + Sync>,
-    project_id: i64,
+    source: EventSource,
     project_path: String,
+    // Scan directories being watched, counted against [watcher] max_directories
+    directories: usize,
+    health: Arc<Mutex<WatchHealth>>,
+}
+
+// Shared between a watch thread and get_watching_projects
+#[derive(Clone, Default)]
+struct WatchHealth {
+    // Set when the watch thread ends without being stopped
+    dead: bool,
+    last_error: Option<String>,
+    last_event_at: Option<String>,
+    last_rescan_at: Option<String>,
+    // "3 added, 1 changed, 0 removed" or "failed: <error>"
+    last_rescan: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -21,103 +58,407 @@ pub struct WatcherStatus {
     project_id: i64,
     is_watching: bool,
     path: String,
+    // "watching", "error" (still running, last event or rescan failed) or "dead"
+    state: String,
+    error: Option<String>,
+    // "native" or "poll"
+    backend: String,
+    last_event_at: Option<String>,
+    last_rescan_at: Option<String>,
+    last_rescan: Option<String>,
 }
 
-// Start watching a project
-#[tauri::command]
-pub fn start_watching_project(project_id: i64, project_path: String, scan_dirs: Vec<String>) -> Result<bool, String> {
-    let mut watchers = WATCHERS.lock().map_err(|e| e.to_string())?;
-    
-    // Check if already watching
-    if watchers.contains_key(&project_id) {
-        return Ok(true); // Already watching
+// Marks the watch as dead if its thread returns or panics while the project is still registered
+struct ThreadGuard {
+    project_id: i64,
+    health: Arc<Mutex<WatchHealth>>,
+}
+
+impl Drop for ThreadGuard {
+    fn drop(&mut self) {
+        let registered = WATCHERS.lock()
+            .map(|watchers| watchers.get(&self.project_id).map(|w| Arc::ptr_eq(&w.health, &self.health)).unwrap_or(false))
+            .unwrap_or(false);
+        if registered {
+            logger::error(&format!("Watcher for project {} stopped unexpectedly", self.project_id));
+            if let Ok(mut health) = self.health.lock() {
+                health.dead = true;
+                let error = health.last_error.get_or_insert_with(|| "Watcher thread exited".to_string());
+                events::publish_watcher_error(self.project_id, error);
+            }
+        }
     }
-    
-    // Create watcher configuration
-    let (tx, rx) = std::sync::mpsc::channel();
+}
+
+fn set_error(project_id: i64, health: &Arc<Mutex<WatchHealth>>, error: Option<String>) {
+    if let Some(e) = &error {
+        logger::error(e);
+        events::publish_watcher_error(project_id, e);
+    }
+    if let Ok(mut health) = health.lock() {
+        health.last_error = error;
+    }
+}
+
+fn record_event(health: &Arc<Mutex<WatchHealth>>) {
+    if let Ok(mut health) = health.lock() {
+        health.last_event_at = Some(Utc::now().to_rfc3339());
+    }
+}
+
+// Paths changed during the current debounce window
+#[derive(Default)]
+struct PendingChanges {
+    upserted: HashSet<PathBuf>,
+    removed: HashSet<PathBuf>,
+    renamed: Vec<(PathBuf, PathBuf)>,
+    // The backend dropped events, so only a full rescan is reliable
+    rescan: bool,
+}
+
+impl PendingChanges {
+    fn is_empty(&self) -> bool {
+        self.upserted.is_empty() && self.removed.is_empty() && self.renamed.is_empty() && !self.rescan
+    }
+
+    fn upsert(&mut self, path: PathBuf) {
+        self.removed.remove(&path);
+        self.upserted.insert(path);
+    }
+
+    fn remove(&mut self, path: PathBuf) {
+        self.upserted.remove(&path);
+        self.removed.insert(path);
+    }
+
+    // Returns whether the event changed anything worth indexing
+    fn record(&mut self, event: notify::Event) -> bool {
+        if event.need_rescan() {
+            self.rescan = true;
+            return true;
+        }
+        let mut paths = event.paths.into_iter();
+        match event.kind {
+            // inotify reports From, To and then Both for a rename inside the watched tree
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => {
+                if let (Some(from), Some(to)) = (paths.next(), paths.next()) {
+                    self.removed.remove(&from);
+                    self.upserted.remove(&to);
+                    self.renamed.push((from, to));
+                }
+            },
+            EventKind::Modify(ModifyKind::Name(RenameMode::From)) | EventKind::Remove(_) => paths.for_each(|p| self.remove(p)),
+            // FSEvents reports each side of a rename on its own
+            EventKind::Modify(ModifyKind::Name(_)) => paths.for_each(|p| if p.exists() { self.upsert(p) } else { self.remove(p) }),
+            EventKind::Modify(ModifyKind::Metadata(_)) => return false,
+            EventKind::Create(_) | EventKind::Modify(_) => paths.for_each(|p| self.upsert(p)),
+            _ => return false,
+        }
+        true
+    }
+}
+
+fn rescan(project_id: i64, project_path: &str, scan_dirs: &[String]) -> Result<files::FileChanges, String> {
+    let settings = crate::db::get_settings()?;
+    files::rescan_project(project_id, project_path, &settings.default_include_patterns, scan_dirs.to_vec())
+}
+
+// Index the changed paths directly; a full rescan only when events were lost or applying them failed
+fn apply_changes(project_id: i64, project_path: &str, scan_dirs: &[String], pending: PendingChanges) -> Result<files::FileChanges, String> {
+    if pending.rescan {
+        logger::warn(&format!("Watcher for project {} lost events, rescanning", project_id));
+        return rescan(project_id, project_path, scan_dirs);
+    }
+
+    let settings = crate::db::get_settings()?;
+    let changes = files::PathChanges {
+        upserted: pending.upserted.into_iter().collect(),
+        removed: pending.removed.into_iter().collect(),
+        renamed: pending.renamed,
+    };
+    match files::apply_path_changes(project_id, project_path, &settings.default_include_patterns, &changes) {
+        Ok(changes) => Ok(changes),
+        Err(e) => {
+            logger::warn(&format!("Incremental update of project {} failed, rescanning: {}", project_id, e));
+            rescan(project_id, project_path, scan_dirs)
+        }
+    }
+}
+
+// Coalesce events until the project has been quiet for the debounce window, then apply them.
+// Runs until the watcher is dropped by stop_watching_project.
+fn watch_loop(
+    project_id: i64,
+    project_path: String,
+    scan_dirs: Vec<String>,
+    rx: Receiver<notify::Result<notify::Event>>,
+    health: Arc<Mutex<WatchHealth>>
+) {
+    let _guard = ThreadGuard { project_id, health: health.clone() };
+    let debounce = Duration::from_millis(config::get_config().watcher.debounce_ms.max(1));
+    let mut pending = PendingChanges::default();
+    let mut pending_since: Option<Instant> = None;
+
+    loop {
+        let timed_out = match rx.recv_timeout(debounce) {
+            Ok(Ok(event)) => {
+                if pending.record(event) {
+                    pending_since.get_or_insert_with(Instant::now);
+                    record_event(&health);
+                }
+                false
+            },
+            Ok(Err(e)) => {
+                record_event(&health);
+                set_error(project_id, &health, Some(format!("Watch error for project {}: {}", project_id, e)));
+                pending.rescan = true;
+                pending_since.get_or_insert_with(Instant::now);
+                false
+            },
+            Err(RecvTimeoutError::Timeout) => true,
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+
+        let overdue = pending_since.map(|since| since.elapsed() >= debounce * MAX_DEBOUNCE_WINDOWS).unwrap_or(false);
+        if pending_since.is_some() && (timed_out || overdue) {
+            pending_since = None;
+            if pending.is_empty() {
+                continue;
+            }
+            let result = apply_changes(project_id, &project_path, &scan_dirs, std::mem::take(&mut pending));
+            if let Ok(mut health) = health.lock() {
+                health.last_rescan_at = Some(Utc::now().to_rfc3339());
+                health.last_rescan = Some(match &result {
+                    Ok(changes) => format!("{} added, {} changed, {} removed", changes.added.len(), changes.changed.len(), changes.removed.len()),
+                    Err(e) => format!("failed: {}", e),
+                });
+            }
+            match result {
+                Ok(_) => set_error(project_id, &health, None),
+                Err(e) => set_error(project_id, &health, Some(format!("Error rescanning project {}: {}", project_id, e))),
+            }
+        }
+    }
+
+    logger::info(&format!("Stopped watching project {}", project_id));
+}
+
+type EventSender = std::sync::mpsc::Sender<notify::Result<notify::Event>>;
+
+fn native_source(watch_paths: &[PathBuf], tx: EventSender) -> Result<EventSource, String> {
     let mut watcher = notify::recommended_watcher(tx).map_err(|e| e.to_string())?;
-    
+    for path in watch_paths {
+        watcher.watch(path, RecursiveMode::Recursive).map_err(|e| e.to_string())?;
+    }
+    Ok(EventSource { _native: Some(Box::new(watcher)), _poller: None, backend: "native" })
+}
+
+fn poll_source(watch_paths: &[PathBuf], tx: EventSender) -> Result<EventSource, String> {
+    let interval = Duration::from_secs(config::get_config().watcher.poll_interval_secs.max(1));
+    let poller = SnapshotPoller::start(watch_paths.to_vec(), interval, tx)?;
+    Ok(EventSource { _native: None, _poller: Some(poller), backend: "poll" })
+}
+
+// Whether a watch for `project_id` is running; a watch whose thread died is dropped
+fn is_watching(watchers: &mut HashMap<i64, ProjectWatcher>, project_id: i64) -> bool {
+    match watchers.get(&project_id) {
+        Some(existing) if !existing.health.lock().map(|h| h.dead).unwrap_or(true) => true,
+        Some(_) => {
+            watchers.remove(&project_id);
+            false
+        },
+        None => false,
+    }
+}
+
+// Err when one more project with `directories` watched directories would exceed the [watcher] limits
+fn check_limits(watchers: &HashMap<i64, ProjectWatcher>, limits: &WatcherConfig, project_id: i64, directories: usize) -> Result<(), String> {
+    if watchers.len() >= limits.max_projects {
+        return Err(format!("Already watching {} projects, the configured maximum", limits.max_projects));
+    }
+    let in_use: usize = watchers.values().map(|w| w.directories).sum();
+    if in_use + directories > limits.max_directories {
+        return Err(format!(
+            "Watching project {} would exceed the limit of {} watched directories ({} in use)",
+            project_id, limits.max_directories, in_use
+        ));
+    }
+    Ok(())
+}
+
+// Start a watch unless the [watcher] limits would be exceeded. Setting up the source can take a
+// while (polling snapshots the whole tree), so it is built without holding WATCHERS and the
+// limits and duplicates are checked again before the watch is stored.
+fn start_watch(project_id: i64, project_path: &str, scan_dirs: Vec<String>, backend: Option<WatcherBackend>) -> Result<bool, String> {
     // Watch each scan directory
-    let project_path_buf = PathBuf::from(&project_path);
-    for dir in &scan_dirs {
-        let watch_path = project_path_buf.join(dir);
-        if watch_path.exists() && watch_path.is_dir() {
-            watcher.watch(&watch_path, RecursiveMode::Recursive).map_err(|e| e.to_string())?;
+    let project_path_buf = PathBuf::from(project_path);
+    let watch_paths: Vec<PathBuf> = scan_dirs.iter()
+        .map(|dir| project_path_buf.join(dir))
+        .filter(|path| path.is_dir())
+        .collect();
+
+    let limits = config::get_config().watcher.clone();
+    {
+        let mut watchers = WATCHERS.lock().map_err(|e| e.to_string())?;
+        if is_watching(&mut watchers, project_id) {
+            return Ok(true);
         }
+        check_limits(&watchers, &limits, project_id, watch_paths.len())?;
     }
-    
-    // Start background thread to handle events
-    let project_id_clone = project_id;
-    let project_path_clone = project_path.clone();
-    let scan_dirs_clone = scan_dirs.clone();
-    
-    std::thread::spawn(move || {
-        for res in rx {
-            match res {
-                Ok(event) => {
-                    match event.kind {
-                        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_) => {
-                            // Debounce: Wait a moment to group multiple changes
-                            std::thread::sleep(std::time::Duration::from_secs(2));
-                            
-                            // Get current settings for scan configuration
-                            if let Ok(settings) = crate::db::get_settings() {
-                                // Trigger a scan when files change
-                                if let Err(e) = files::scan_project(
-                                    project_id_clone,
-                                    project_path_clone.clone(),
-                                    settings.default_include_patterns,
-                                    scan_dirs_clone.clone()
-                                ) {
-                                    eprintln!("Error rescanning project {}: {}", project_id_clone, e);
-                                }
-                            }
-                            
-                            // Stop after first event to avoid rescanning multiple times
-                            break;
-                        },
-                        _ => {} // Ignore other events
-                    }
-                },
-                Err(e) => eprintln!("Watch error: {:?}", e),
+
+    // OS notifications miss changes other machines make on network shares, so those are polled
+    let (tx, rx) = std::sync::mpsc::channel();
+    let source = match backend.unwrap_or(limits.backend) {
+        WatcherBackend::Poll => poll_source(&watch_paths, tx)?,
+        WatcherBackend::Auto if paths::is_network_path(project_path) => poll_source(&watch_paths, tx)?,
+        WatcherBackend::Native => native_source(&watch_paths, tx)?,
+        WatcherBackend::Auto => match native_source(&watch_paths, tx.clone()) {
+            Ok(source) => source,
+            Err(e) => {
+                logger::warn(&format!("OS notifications unavailable for project {}, polling instead: {}", project_id, e));
+                poll_source(&watch_paths, tx)?
             }
-        }
-    });
-    
+        },
+    };
+
+    // Another call may have started this project or used up the limits meanwhile; dropping
+    // the unused source stops it
+    let mut watchers = WATCHERS.lock().map_err(|e| e.to_string())?;
+    if is_watching(&mut watchers, project_id) {
+        return Ok(true);
+    }
+    check_limits(&watchers, &limits, project_id, watch_paths.len())?;
+
+    // Start background thread to handle events
+    let health = Arc::new(Mutex::new(WatchHealth::default()));
+    let thread_health = health.clone();
+    let thread_path = project_path.to_string();
+    std::thread::spawn(move || watch_loop(project_id, thread_path, scan_dirs, rx, thread_health));
+
     // Store the watcher
+    logger::info(&format!("Watching project {} ({})", project_id, source.backend));
     watchers.insert(project_id, ProjectWatcher {
-        watcher: Box::new(watcher),
-        project_id,
-        project_path,
+        source,
+        project_path: project_path.to_string(),
+        directories: watch_paths.len(),
+        health,
     });
-    
+
     Ok(true)
 }
 
-// Stop watching a project
+// Start watching a project. `backend` overrides the configured [watcher] backend for this project.
+// The watch is saved for this machine and restored at the next start.
+#[tauri::command]
+pub fn start_watching_project(
+    project_id: i64,
+    project_path: String,
+    scan_dirs: Vec<String>,
+    backend: Option<WatcherBackend>,
+    user_id: Option<i64>
+) -> Result<bool, String> {
+    let started = start_watch(project_id, &project_path, scan_dirs.clone(), backend)?;
+
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO project_watches (project_id, machine, user_id, scan_dirs, backend, created_at) VALUES (?, ?, ?, ?, ?, ?)
+         ON CONFLICT(project_id, machine) DO UPDATE SET user_id = excluded.user_id, scan_dirs = excluded.scan_dirs, backend = excluded.backend",
+        params![
+            project_id,
+            paths::get_machine_name(),
+            user_id,
+            serde_json::to_string(&scan_dirs).map_err(|e| e.to_string())?,
+            backend.map(|b| serde_json::to_string(&b)).transpose().map_err(|e| e.to_string())?,
+            Utc::now().to_rfc3339()
+        ]
+    ).map_err(|e| format!("Failed to save watch for project {}: {}", project_id, e))?;
+
+    Ok(started)
+}
+
+// Stop watching a project, here and at future starts of this machine
 #[tauri::command]
 pub fn stop_watching_project(project_id: i64) -> Result<bool, String> {
-    let mut watchers = WATCHERS.lock().map_err(|e| e.to_string())?;
-    
-    if watchers.remove(&project_id).is_some() {
-        Ok(true)
-    } else {
-        Ok(false) // Not watching
+    let removed = WATCHERS.lock().map_err(|e| e.to_string())?.remove(&project_id).is_some();
+
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM project_watches WHERE project_id = ? AND machine = ?",
+        params![project_id, paths::get_machine_name()]
+    ).map_err(|e| format!("Failed to remove watch for project {}: {}", project_id, e))?;
+
+    Ok(removed)
+}
+
+// Restart the watches saved for this machine. Run on a background thread at startup, since
+// snapshotting projects on network shares can take a while.
+pub fn restore_watches() {
+    let saved = match saved_watches() {
+        Ok(saved) => saved,
+        Err(e) => {
+            logger::error(&format!("Failed to load saved watches: {}", e));
+            return;
+        }
+    };
+    logger::info(&format!("Restoring {} saved watches", saved.len()));
+
+    for (project_id, project_path, scan_dirs, backend) in saved {
+        if let Err(e) = start_watch(project_id, &project_path, scan_dirs, backend) {
+            logger::warn(&format!("Could not restore watch on project {}: {}", project_id, e));
+            events::publish_watcher_error(project_id, &e);
+        }
     }
 }
 
+type SavedWatch = (i64, String, Vec<String>, Option<WatcherBackend>);
+
+fn saved_watches() -> Result<Vec<SavedWatch>, String> {
+    let conn = db::get_connection().map_err(|e| e.to_string())?;
+    let mut stmt = conn.prepare(
+        "SELECT w.project_id, p.path, w.scan_dirs, w.backend FROM project_watches w
+         JOIN projects p ON p.id = w.project_id
+         WHERE w.machine = ? ORDER BY w.created_at"
+    ).map_err(|e| e.to_string())?;
+    let rows = stmt.query_map(params![paths::get_machine_name()], |row| {
+        Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?, row.get::<_, Option<String>>(3)?))
+    }).map_err(|e| e.to_string())?;
+
+    let mut saved = Vec::new();
+    for row in rows {
+        let (project_id, path, scan_dirs, backend) = row.map_err(|e| e.to_string())?;
+        let scan_dirs: Vec<String> = serde_json::from_str(&scan_dirs).unwrap_or_default();
+        let backend = backend.and_then(|b| serde_json::from_str(&b).ok());
+        saved.push((project_id, paths::normalize_path(&path), scan_dirs, backend));
+    }
+    Ok(saved)
+}
+
 // Get all watching projects
 #[tauri::command]
 pub fn get_watching_projects() -> Result<Vec<WatcherStatus>, String> {
     let watchers = WATCHERS.lock().map_err(|e| e.to_string())?;
-    
+
     let status: Vec<WatcherStatus> = watchers.iter().map(|(id, watcher)| {
+        let health = watcher.health.lock().map(|h| h.clone()).unwrap_or_default();
+        let state = if health.dead {
+            "dead"
+        } else if health.last_error.is_some() {
+            "error"
+        } else {
+            "watching"
+        };
         WatcherStatus {
             project_id: *id,
-            is_watching: true,
+            is_watching: !health.dead,
             path: watcher.project_path.clone(),
+            state: state.to_string(),
+            error: health.last_error,
+            backend: watcher.source.backend.to_string(),
+            last_event_at: health.last_event_at,
+            last_rescan_at: health.last_rescan_at,
+            last_rescan: health.last_rescan,
         }
     }).collect();
-    
+
     Ok(status)
 }
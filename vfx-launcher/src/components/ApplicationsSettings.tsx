@@ -0,0 +1,134 @@
+import React, { useState, useEffect } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { useAuth } from '../context/AuthContext';
+import { Application } from '../types/application';
+import Button from './Button';
+
+const emptyApplication: Application = {
+  id: 0,
+  name: '',
+  version: '',
+  windows_path: '',
+  macos_path: '',
+  linux_path: '',
+  default_args: [],
+  file_types: [],
+};
+
+/**
+ * Editor for the application registry (one row per DCC name and version)
+ */
+const ApplicationsSettings: React.FC = () => {
+  const { user } = useAuth();
+  const [applications, setApplications] = useState<Application[]>([]);
+  const [editing, setEditing] = useState<Application | null>(null);
+  const [rawArgs, setRawArgs] = useState<string>('');
+  const [rawFileTypes, setRawFileTypes] = useState<string>('');
+  const [error, setError] = useState<string | null>(null);
+
+  const loadApplications = async () => {
+    try {
+      const apps: Application[] = await invoke('get_applications');
+      setApplications(apps);
+    } catch (err) {
+      setError(`Failed to load applications: ${err instanceof Error ? err.message : String(err)}`);
+    }
+  };
+
+  useEffect(() => { loadApplications(); }, []);
+
+  const startEditing = (app: Application) => {
+    setEditing(app);
+    setRawArgs(app.default_args.join(' '));
+    setRawFileTypes(app.file_types.join(', '));
+    setError(null);
+  };
+
+  const handleChange = (e: React.ChangeEvent<HTMLInputElement>) => {
+    const { name, value } = e.target;
+    setEditing(prev => prev ? { ...prev, [name]: value } : prev);
+  };
+
+  const handleSave = async () => {
+    if (!editing) return;
+    try {
+      await invoke('save_application', {
+        application: {
+          ...editing,
+          default_args: rawArgs.split(' ').map(s => s.trim()).filter(Boolean),
+          file_types: rawFileTypes.split(',').map(s => s.trim()).filter(Boolean),
+        },
+        actingUserId: user?.id,
+      });
+      setEditing(null);
+      await loadApplications();
+    } catch (err) {
+      setError(`Failed to save application: ${err instanceof Error ? err.message : String(err)}`);
+    }
+  };
+
+  const handleDelete = async (id: number) => {
+    try {
+      await invoke('delete_application', { id, actingUserId: user?.id });
+      await loadApplications();
+    } catch (err) {
+      setError(`Failed to delete application: ${err instanceof Error ? err.message : String(err)}`);
+    }
+  };
+
+  return (
+    <div className="space-y-2">
+      <h2 className="text-lg font-semibold">Applications</h2>
+      {error && <p className="text-red-600 dark:text-red-400">{error}</p>}
+      <table className="w-full text-sm">
+        <thead>
+          <tr className="text-left text-gray-500">
+            <th>Name</th>
+            <th>Version</th>
+            <th>File types</th>
+            <th>Arguments</th>
+            <th></th>
+          </tr>
+        </thead>
+        <tbody>
+          {applications.map(app => (
+            <tr key={app.id}>
+              <td>{app.name}</td>
+              <td>{app.version}</td>
+              <td>{app.file_types.join(', ')}</td>
+              <td>{app.default_args.join(' ')}</td>
+              <td className="space-x-2 text-right">
+                <Button type="button" size="small" variant="secondary" onClick={() => startEditing(app)}>Edit</Button>
+                <Button type="button" size="small" variant="danger" onClick={() => handleDelete(app.id)}>Delete</Button>
+              </td>
+            </tr>
+          ))}
+        </tbody>
+      </table>
+
+      {editing ? (
+        <div className="space-y-2 border border-gray-600 rounded p-3">
+          <div className="flex space-x-2">
+            <input name="name" value={editing.name} onChange={handleChange} className="input-field w-1/2" placeholder="Name, e.g. Nuke" />
+            <input name="version" value={editing.version} onChange={handleChange} className="input-field w-1/2" placeholder="Version, e.g. 15.1v3" />
+          </div>
+          <input name="windows_path" value={editing.windows_path || ''} onChange={handleChange} className="input-field w-full" placeholder="Windows executable, e.g. C:\Program Files\Nuke15.1v3\Nuke15.1.exe" />
+          <input name="macos_path" value={editing.macos_path || ''} onChange={handleChange} className="input-field w-full" placeholder="macOS executable, e.g. /Applications/Nuke15.1v3/Nuke15.1v3.app" />
+          <input name="linux_path" value={editing.linux_path || ''} onChange={handleChange} className="input-field w-full" placeholder="Linux executable, e.g. /usr/local/Nuke15.1v3/Nuke15.1" />
+          <div className="flex space-x-2">
+            <input value={rawFileTypes} onChange={e => setRawFileTypes(e.target.value)} className="input-field w-1/2" placeholder="File types, e.g. nk" />
+            <input value={rawArgs} onChange={e => setRawArgs(e.target.value)} className="input-field w-1/2" placeholder="Arguments, e.g. --nukex {file}" />
+          </div>
+          <div className="space-x-2">
+            <Button type="button" size="small" onClick={handleSave}>Save Application</Button>
+            <Button type="button" size="small" variant="secondary" onClick={() => setEditing(null)}>Cancel</Button>
+          </div>
+        </div>
+      ) : (
+        <Button type="button" size="small" onClick={() => startEditing(emptyApplication)}>Add Application</Button>
+      )}
+    </div>
+  );
+};
+
+export default ApplicationsSettings;
@@ -0,0 +1,116 @@
+import React, { useState, useEffect } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { useAuth } from '../context/AuthContext';
+import { ConfigValue } from '../types/config';
+import Button from './Button';
+
+const SECTIONS = ['network', 'database', 'paths', 'locks', 'session_logs', 'nuke_paths', 'watcher', 'logging', 'activity', 'path_mappings'];
+
+/**
+ * Admin view of the layered configuration: load problems, where each value comes from,
+ * and a JSON editor for one section at a time (written to the user config file)
+ */
+const ConfigurationSettings: React.FC = () => {
+  const { user, isAdmin } = useAuth();
+  const [configErrors, setConfigErrors] = useState<string[]>([]);
+  const [values, setValues] = useState<ConfigValue[]>([]);
+  const [section, setSection] = useState<string>('locks');
+  const [rawSection, setRawSection] = useState<string>('');
+  const [error, setError] = useState<string | null>(null);
+  const [message, setMessage] = useState<string | null>(null);
+
+  const loadConfig = async () => {
+    try {
+      setConfigErrors(await invoke('get_config_errors'));
+      setValues(await invoke('get_config_sources'));
+    } catch (err) {
+      setError(`Failed to load configuration: ${err instanceof Error ? err.message : String(err)}`);
+    }
+  };
+
+  const loadSection = async (name: string) => {
+    if (!user) return;
+    try {
+      const value = await invoke('get_config_section', { section: name, actingUserId: user.id });
+      setRawSection(JSON.stringify(value, null, 2));
+    } catch (err) {
+      setError(`Failed to load ${name}: ${err instanceof Error ? err.message : String(err)}`);
+    }
+  };
+
+  useEffect(() => { loadConfig(); }, []);
+  useEffect(() => { loadSection(section); }, [section, user?.id]);
+
+  if (!isAdmin()) return null;
+
+  const handleReload = async () => {
+    setError(null);
+    setMessage(null);
+    try {
+      await invoke('reload_config');
+      setMessage('Configuration reloaded');
+      await loadConfig();
+      await loadSection(section);
+    } catch (err) {
+      setError(`Configuration not reloaded: ${err instanceof Error ? err.message : String(err)}`);
+    }
+  };
+
+  const handleSave = async () => {
+    if (!user) return;
+    setError(null);
+    setMessage(null);
+    try {
+      const parsed = JSON.parse(rawSection);
+      await invoke('update_config_section', { section, values: parsed, actingUserId: user.id });
+      setMessage(`Saved ${section}`);
+      await loadConfig();
+    } catch (err) {
+      setError(`Failed to save ${section}: ${err instanceof Error ? err.message : String(err)}`);
+    }
+  };
+
+  return (
+    <div className="space-y-2">
+      <h2 className="text-lg font-semibold">Configuration</h2>
+      {configErrors.map(e => <p key={e} className="text-red-600 dark:text-red-400">{e}</p>)}
+      {error && <p className="text-red-600 dark:text-red-400">{error}</p>}
+      {message && <p className="text-green-600 dark:text-green-400">{message}</p>}
+
+      <div className="flex space-x-2 items-center">
+        <select value={section} onChange={e => setSection(e.target.value)} className="input-field">
+          {SECTIONS.map(name => <option key={name} value={name}>{name}</option>)}
+        </select>
+        <Button type="button" size="small" onClick={handleSave}>Save Section</Button>
+        <Button type="button" size="small" variant="secondary" onClick={handleReload}>Reload</Button>
+      </div>
+      <textarea
+        value={rawSection}
+        onChange={e => setRawSection(e.target.value)}
+        rows={10}
+        className="input-field w-full font-mono text-xs"
+      />
+
+      <table className="w-full text-sm">
+        <thead>
+          <tr className="text-left text-gray-500">
+            <th>Key</th>
+            <th>Value</th>
+            <th>Source</th>
+          </tr>
+        </thead>
+        <tbody>
+          {values.filter(v => v.key.split('.')[0] === section).map(v => (
+            <tr key={v.key}>
+              <td>{v.key}</td>
+              <td className="font-mono text-xs">{JSON.stringify(v.value)}</td>
+              <td className="text-gray-500">{v.source}</td>
+            </tr>
+          ))}
+        </tbody>
+      </table>
+    </div>
+  );
+};
+
+export default ConfigurationSettings;
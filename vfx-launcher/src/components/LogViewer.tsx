@@ -0,0 +1,92 @@
+import React, { useState, useEffect } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { useAuth } from '../context/AuthContext';
+import { LogEntry } from '../types/logs';
+import Button from './Button';
+
+const LEVEL_CLASSES: Record<string, string> = {
+  ERROR: 'text-red-600 dark:text-red-400',
+  WARN: 'text-yellow-600 dark:text-yellow-400',
+  INFO: 'text-gray-700 dark:text-gray-300',
+  DEBUG: 'text-gray-500',
+  TRACE: 'text-gray-400',
+};
+
+/**
+ * Recent application log entries with level, time and text filters, and an export
+ * of the diagnostics bundle to attach to support tickets
+ */
+const LogViewer: React.FC = () => {
+  const { user, isAdmin } = useAuth();
+  const [entries, setEntries] = useState<LogEntry[]>([]);
+  const [level, setLevel] = useState<string>('info');
+  const [text, setText] = useState<string>('');
+  const [since, setSince] = useState<string>('');
+  const [error, setError] = useState<string | null>(null);
+  const [bundlePath, setBundlePath] = useState<string | null>(null);
+
+  const loadEntries = async () => {
+    setError(null);
+    try {
+      const filter = { level, text: text || undefined, since: since || undefined, limit: 300 };
+      setEntries(await invoke<LogEntry[]>('tail_logs', { filter, actingUserId: user?.id }));
+    } catch (err) {
+      setError(`Failed to read logs: ${err instanceof Error ? err.message : String(err)}`);
+    }
+  };
+
+  useEffect(() => { if (isAdmin()) loadEntries(); }, [level]);
+
+  const handleExport = async () => {
+    setError(null);
+    setBundlePath(null);
+    try {
+      setBundlePath(await invoke<string>('export_diagnostics', { actingUserId: user?.id }));
+    } catch (err) {
+      setError(`Failed to export diagnostics: ${err instanceof Error ? err.message : String(err)}`);
+    }
+  };
+
+  if (!isAdmin()) return null;
+
+  return (
+    <div className="space-y-2">
+      <h2 className="text-lg font-semibold">Logs</h2>
+      {error && <p className="text-red-600 dark:text-red-400">{error}</p>}
+      {bundlePath && <p className="text-green-600 dark:text-green-400">Diagnostics saved to {bundlePath}</p>}
+
+      <div className="flex space-x-2 items-center">
+        <select value={level} onChange={e => setLevel(e.target.value)} className="input-field">
+          {['error', 'warn', 'info', 'debug', 'trace'].map(name => <option key={name} value={name}>{name}</option>)}
+        </select>
+        <input
+          type="text"
+          value={text}
+          onChange={e => setText(e.target.value)}
+          onKeyDown={e => { if (e.key === 'Enter') loadEntries(); }}
+          placeholder="Search"
+          className="input-field flex-1"
+        />
+        <input
+          type="datetime-local"
+          value={since}
+          onChange={e => setSince(e.target.value)}
+          className="input-field"
+        />
+        <Button type="button" size="small" onClick={loadEntries}>Refresh</Button>
+        <Button type="button" size="small" variant="secondary" onClick={handleExport}>Export Diagnostics</Button>
+      </div>
+
+      <div className="h-80 overflow-auto bg-gray-50 dark:bg-gray-900 rounded p-2 font-mono text-xs">
+        {entries.length === 0 && <p className="text-gray-500">No matching entries</p>}
+        {entries.map((entry, index) => (
+          <div key={`${entry.file}-${index}`} className={`whitespace-pre-wrap ${LEVEL_CLASSES[entry.level] ?? ''}`}>
+            {entry.timestamp} {entry.level} {entry.module ? `[${entry.module}] ` : ''}{entry.message}
+          </div>
+        ))}
+      </div>
+    </div>
+  );
+};
+
+export default LogViewer;
@@ -1,7 +1,7 @@
 import React, { useState, useEffect } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { useAuth } from '../context/AuthContext';
-import { UserActivity } from '../types/activity';
+import { UserActivity, ActivityPage as ActivityPageResult, ActivityExport, ActivityArchive, ExportFormat } from '../types/activity';
 import { formatDistanceToNow, format } from 'date-fns';
 import { Link } from 'react-router-dom';
 
@@ -13,6 +13,11 @@ const ActivityPage: React.FC = () => {
   const [error, setError] = useState<string | null>(null);
   const [activityFilter, setActivityFilter] = useState<string | null>(null);
   const [userFilter, setUserFilter] = useState<number | null>(null);
+  const [since, setSince] = useState('');
+  const [until, setUntil] = useState('');
+  const [nextCursor, setNextCursor] = useState<string | null>(null);
+  const [isLoadingMore, setIsLoadingMore] = useState(false);
+  const [message, setMessage] = useState<string | null>(null);
   
   // Activity filters for dropdown
   const activityTypes = [
@@ -25,27 +30,68 @@ const ActivityPage: React.FC = () => {
     { value: 'remove_favorite', label: 'Remove Favorite' },
     { value: 'add_user', label: 'Add User' },
     { value: 'update_user', label: 'Update User' },
-    { value: 'delete_user', label: 'Delete User' }
+    { value: 'delete_user', label: 'Delete User' },
+    { value: 'checkout_file', label: 'Check Out File' },
+    { value: 'checkin_file', label: 'Check In File' },
+    { value: 'version_up', label: 'Version Up' },
+    { value: 'new_script', label: 'New Script' },
+    { value: 'session_end', label: 'Session End' }
   ];
 
   useEffect(() => {
     fetchActivities();
-  }, [activityFilter, userFilter]);
+  }, [activityFilter, userFilter, since, until]);
+
+  // Admins can see all activities or filter by user; the backend limits everyone else to their own
+  const currentQuery = (cursor: string | null) => ({
+    user_id: userFilter,
+    activity_types: activityFilter ? [activityFilter] : [],
+    since: since || null,
+    until: until || null,
+    cursor,
+    limit: 100
+  });
+
+  const fetchPage = (cursor: string | null) =>
+    invoke('get_activity', { query: currentQuery(cursor), actingUserId: user?.id }) as Promise<ActivityPageResult>;
+
+  // Exports everything matching the current filters, not just the loaded pages
+  const handleExport = async (format: ExportFormat) => {
+    setMessage(null);
+    try {
+      const result = await invoke<ActivityExport>('export_activity', {
+        query: currentQuery(null),
+        format,
+        outputPath: null,
+        actingUserId: user?.id
+      });
+      setMessage(`Exported ${result.rows} activities to ${result.path}`);
+    } catch (err) {
+      setError(`Failed to export activity: ${err instanceof Error ? err.message : String(err)}`);
+    }
+  };
+
+  // Runs the configured retention policy now instead of waiting for the schedule
+  const handleArchive = async () => {
+    if (!confirm('Archive and remove activity older than the configured retention period?')) return;
+    setMessage(null);
+    try {
+      const archive = await invoke<ActivityArchive | null>('archive_activity', { olderThanDays: null, actingUserId: user?.id });
+      setMessage(archive ? `Archived ${archive.rows} activities to ${archive.path}` : 'No activity old enough to archive');
+      fetchActivities();
+    } catch (err) {
+      setError(`Failed to archive activity: ${err instanceof Error ? err.message : String(err)}`);
+    }
+  };
 
   const fetchActivities = async () => {
     setIsLoading(true);
     setError(null);
     
     try {
-      // If admin, can see all activities or filter by user
-      // If regular user, can only see their own
-      const fetchedActivities = await invoke('get_activity_logs', {
-        userId: !isAdmin ? user?.id : userFilter,
-        limit: 100,
-        activityType: activityFilter
-      }) as UserActivity[];
-      
-      setActivities(fetchedActivities);
+      const page = await fetchPage(null);
+      setActivities(page.records);
+      setNextCursor(page.next_cursor);
     } catch (err) {
       console.error('Failed to fetch activities:', err);
       setError(`Failed to load activity logs: ${err instanceof Error ? err.message : String(err)}`);
@@ -53,6 +99,21 @@ const ActivityPage: React.FC = () => {
       setIsLoading(false);
     }
   };
+
+  const loadMore = async () => {
+    if (!nextCursor) return;
+    setIsLoadingMore(true);
+    try {
+      const page = await fetchPage(nextCursor);
+      setActivities(prev => [...prev, ...page.records]);
+      setNextCursor(page.next_cursor);
+    } catch (err) {
+      console.error('Failed to fetch more activities:', err);
+      setError(`Failed to load activity logs: ${err instanceof Error ? err.message : String(err)}`);
+    } finally {
+      setIsLoadingMore(false);
+    }
+  };
   
   // Get appropriate icon for each activity type
   const getActivityIcon = (type: string) => {
@@ -132,6 +193,22 @@ const ActivityPage: React.FC = () => {
               </option>
             ))}
           </select>
+
+          {/* Date range; "to" is exclusive */}
+          <input
+            type="date"
+            value={since}
+            onChange={(e) => setSince(e.target.value)}
+            title="From"
+            className="rounded border-gray-300 dark:border-gray-700 dark:bg-gray-800 px-3 py-2 focus:outline-none focus:ring-2 focus:ring-blue-500"
+          />
+          <input
+            type="date"
+            value={until}
+            onChange={(e) => setUntil(e.target.value)}
+            title="To (exclusive)"
+            className="rounded border-gray-300 dark:border-gray-700 dark:bg-gray-800 px-3 py-2 focus:outline-none focus:ring-2 focus:ring-blue-500"
+          />
           
           {/* User Filter (Admin only) */}
           {isAdmin && (
@@ -146,9 +223,31 @@ const ActivityPage: React.FC = () => {
               {userFilter !== null ? 'My Activities' : 'All Users'}
             </button>
           )}
+
+          <button
+            onClick={() => handleExport('csv')}
+            className="px-4 py-2 rounded-md bg-gray-200 text-gray-800 hover:bg-gray-300 dark:bg-gray-700 dark:text-gray-200 dark:hover:bg-gray-600 transition-colors"
+          >
+            Export CSV
+          </button>
+          <button
+            onClick={() => handleExport('json')}
+            className="px-4 py-2 rounded-md bg-gray-200 text-gray-800 hover:bg-gray-300 dark:bg-gray-700 dark:text-gray-200 dark:hover:bg-gray-600 transition-colors"
+          >
+            Export JSON
+          </button>
+          {isAdmin && (
+            <button
+              onClick={handleArchive}
+              className="px-4 py-2 rounded-md bg-gray-200 text-gray-800 hover:bg-gray-300 dark:bg-gray-700 dark:text-gray-200 dark:hover:bg-gray-600 transition-colors"
+            >
+              Archive Old
+            </button>
+          )}
         </div>
       </div>
       
+      {message && <p className="text-green-600 dark:text-green-400 mb-4">{message}</p>}
       {isLoading && <p>Loading activity logs...</p>}
       {error && <p className="text-red-500">{error}</p>}
       
@@ -205,6 +304,17 @@ const ActivityPage: React.FC = () => {
                   </div>
                 </div>
               ))}
+              {nextCursor && (
+                <div className="text-center">
+                  <button
+                    onClick={loadMore}
+                    disabled={isLoadingMore}
+                    className="px-4 py-2 rounded-md bg-gray-200 text-gray-800 hover:bg-gray-300 dark:bg-gray-700 dark:text-gray-200 dark:hover:bg-gray-600 transition-colors disabled:opacity-50"
+                  >
+                    {isLoadingMore ? 'Loading...' : 'Load more'}
+                  </button>
+                </div>
+              )}
             </div>
           )}
         </>
@@ -1,11 +1,15 @@
 import React, { useState, useEffect } from 'react';
 import { useParams, useNavigate, Link } from 'react-router-dom';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import { open } from '@tauri-apps/plugin-shell';
 import { useAuth } from '../context/AuthContext';
 import { Project } from '../types/project';
 import { ProjectFile } from '../types/projectFile';
 import { AppSettings } from '../types/settings';
+import { LaunchVariant } from '../types/application';
+import { ProjectFilesChanged, WatcherErrorEvent } from '../types/watcher';
+import { OpenedFile } from '../types/session';
 import Button from '../components/Button';
 import Card from '../components/Card';
 import { formatDistanceToNow } from 'date-fns';
@@ -20,6 +24,7 @@ const ProjectDetailPage: React.FC = () => {
   const [isLoading, setIsLoading] = useState<boolean>(true);
   const [error, setError] = useState<string | null>(null);
   const [isWatching, setIsWatching] = useState(false);
+  const [watcherError, setWatcherError] = useState<string | null>(null);
   
   // State for the selected file version in each group (fileType:folder:fileName -> version)
   const [selectedVersions, setSelectedVersions] = useState<Record<string, string>>({});
@@ -28,6 +33,26 @@ const ProjectDetailPage: React.FC = () => {
   const [showAfterEffects, setShowAfterEffects] = useState<boolean>(true);
   // State for tracking expanded folders
   const [expandedFolders, setExpandedFolders] = useState<Record<string, boolean>>({});
+  // Launch variants for Nuke scripts ('' uses the application's default arguments)
+  const [nukeVariants, setNukeVariants] = useState<LaunchVariant[]>([]);
+  const [nukeVariant, setNukeVariant] = useState<string>('');
+
+  useEffect(() => {
+    invoke<LaunchVariant[]>('get_launch_variants', {})
+      .then(variants => {
+        // Variants are per application name; offer each variant name once
+        const seen = new Set<string>();
+        const unique: LaunchVariant[] = [];
+        variants.forEach(v => {
+          if (v.application_name.toLowerCase().includes('nuke') && !seen.has(v.name)) {
+            seen.add(v.name);
+            unique.push(v);
+          }
+        });
+        setNukeVariants(unique);
+      })
+      .catch(err => console.error('Error loading launch variants:', err));
+  }, []);
 
   useEffect(() => {
     if (!projectId) return;
@@ -49,7 +74,7 @@ const ProjectDetailPage: React.FC = () => {
         
         // Fetch the project files
         console.log(`Fetching files for project ${projectId}`);
-        const fetchedFiles: ProjectFile[] = await invoke('get_project_files', { projectId: projectIdNum });
+        const fetchedFiles: ProjectFile[] = await invoke('get_project_files', { projectId: projectIdNum, userId: user?.id });
         console.log(`Received ${fetchedFiles.length} files`);
         
         // If no files were found and project path exists, trigger a scan
@@ -65,7 +90,7 @@ const ProjectDetailPage: React.FC = () => {
             });
             
             // Fetch files again after scanning
-            const rescannedFiles: ProjectFile[] = await invoke('get_project_files', { projectId: projectIdNum });
+            const rescannedFiles: ProjectFile[] = await invoke('get_project_files', { projectId: projectIdNum, userId: user?.id });
             console.log(`After scan: found ${rescannedFiles.length} files`);
             setFiles(rescannedFiles);
           } catch (scanErr) {
@@ -93,9 +118,6 @@ const ProjectDetailPage: React.FC = () => {
     try {
       console.log(`Opening file: ${file.filename} (${file.file_type}) at path: ${file.path}`);
       
-      // Get app settings to retrieve configured paths
-      const settings: AppSettings = await invoke('get_settings');
-      
       // Log the activity if user is logged in
       if (user) {
         await invoke('log_activity', {
@@ -107,14 +129,13 @@ const ProjectDetailPage: React.FC = () => {
         });
       }
       
-      // Determine which application to use
-      let appPath = '';
-      if (file.file_type === 'nk' && settings.nuke_executable_path) {
-        appPath = settings.nuke_executable_path;
-        await invoke('open_file', { app_path: appPath, file_path: file.path });
-      } else if (file.file_type === 'aep' && settings.ae_executable_path) {
-        appPath = settings.ae_executable_path;
-        await invoke('open_file', { app_path: appPath, file_path: file.path });
+      if (file.file_type === 'nk' || file.file_type === 'aep') {
+        // The backend picks the application from the registry by file type
+        const variant = file.file_type === 'nk' && nukeVariant ? nukeVariant : undefined;
+        const opened = await invoke<OpenedFile>('open_file', { filePath: file.path, userId: user?.id, fileId: file.id, variant });
+        if (opened.warning) {
+          alert(`Opened anyway: ${opened.warning}`);
+        }
       } else {
         // Fallback to OS default application
         await open(file.path);
@@ -128,6 +149,19 @@ const ProjectDetailPage: React.FC = () => {
     }
   };
 
+  // Copy the file to the next free version, index it and open it
+  const handleVersionUp = async (file: ProjectFile, group: string, folder: string, fileName: string) => {
+    try {
+      const newFile: ProjectFile = await invoke('version_up', { fileId: file.id, userId: user?.id, launch: true });
+      setFiles(prev => [...prev, newFile]);
+      handleVersionChange(group, folder, fileName, newFile.version);
+    } catch (err) {
+      const errorMsg = err instanceof Error ? err.message : String(err);
+      alert(`Failed to version up: ${errorMsg}`);
+      console.error('Error versioning up file:', err);
+    }
+  };
+
   // Group files by file type, parent folder, and filename with version tracking
   const groupedFiles = React.useMemo(() => {
     // Structure will be: fileType -> parentFolder -> baseName -> [files]
@@ -265,7 +299,8 @@ const ProjectDetailPage: React.FC = () => {
       
       // Then get the updated files
       const refreshedFiles: ProjectFile[] = await invoke('get_project_files', { 
-        projectId: parseInt(projectId, 10) 
+        projectId: parseInt(projectId, 10),
+        userId: user?.id
       });
       
       console.log(`Found ${refreshedFiles.length} files`);
@@ -294,15 +329,45 @@ const ProjectDetailPage: React.FC = () => {
         const started = await invoke<boolean>('start_watching_project', {
           projectId: parseInt(projectId, 10),
           projectPath: project.path,
-          scanDirs: settings.default_scan_subdirs
+          scanDirs: settings.default_scan_subdirs,
+          userId: user?.id
         });
         setIsWatching(started);
+        setWatcherError(null);
       }
     } catch (err) {
       console.error('Error toggling file watcher:', err);
+      // e.g. the configured limit of watched projects was reached
+      setWatcherError(err instanceof Error ? err.message : String(err));
     }
   };
   
+  // Reload the file list when this or another workstation re-indexes the project
+  useEffect(() => {
+    if (!projectId) return;
+    const projectIdNum = parseInt(projectId, 10);
+
+    const unlistenChanges = listen<ProjectFilesChanged>('project-files-changed', async event => {
+      if (event.payload.project_id !== projectIdNum) return;
+      try {
+        const updatedFiles: ProjectFile[] = await invoke('get_project_files', { projectId: projectIdNum, userId: user?.id });
+        setFiles(updatedFiles);
+        setWatcherError(null);
+      } catch (err) {
+        console.error('Error reloading files after change:', err);
+      }
+    });
+    const unlistenErrors = listen<WatcherErrorEvent>('watcher-error', event => {
+      if (event.payload.project_id !== projectIdNum) return;
+      setWatcherError(event.payload.error);
+    });
+
+    return () => {
+      unlistenChanges.then(unlisten => unlisten());
+      unlistenErrors.then(unlisten => unlisten());
+    };
+  }, [projectId, user?.id]);
+
   // Check if this project is already being watched when component mounts
   useEffect(() => {
     if (!projectId) return;
@@ -442,6 +507,9 @@ const ProjectDetailPage: React.FC = () => {
           </div>
 
           <div className="flex gap-2 items-start">
+            {watcherError && (
+              <span className="text-xs text-red-600 dark:text-red-400 self-center" title={watcherError}>Watcher error</span>
+            )}
             <Button 
               variant={isWatching ? "warning" : "success"}
               size="small"
@@ -498,6 +566,17 @@ const ProjectDetailPage: React.FC = () => {
               </span>
             </Button>
           </div>
+          {nukeVariants.length > 0 && (
+            <>
+              <div className="text-sm font-medium text-gray-700 dark:text-gray-300">Open Nuke as:</div>
+              <select value={nukeVariant} onChange={e => setNukeVariant(e.target.value)} className="input-field w-auto">
+                <option value="">Default</option>
+                {nukeVariants.map(v => (
+                  <option key={v.id} value={v.name}>{v.name}</option>
+                ))}
+              </select>
+            </>
+          )}
         </div>
       )}
 
@@ -582,6 +661,17 @@ const ProjectDetailPage: React.FC = () => {
                                   >
                                     Open
                                   </Button>
+                                  <Button
+                                    variant="secondary"
+                                    size="small"
+                                    onClick={() => {
+                                      if (selectedFile) {
+                                        handleVersionUp(selectedFile, 'nuke', folder, fileName);
+                                      }
+                                    }}
+                                  >
+                                    Version Up
+                                  </Button>
                                 </div>
                               </div>
                               
@@ -683,6 +773,17 @@ const ProjectDetailPage: React.FC = () => {
                                   >
                                     Open
                                   </Button>
+                                  <Button
+                                    variant="secondary"
+                                    size="small"
+                                    onClick={() => {
+                                      if (selectedFile) {
+                                        handleVersionUp(selectedFile, 'aep', folder, fileName);
+                                      }
+                                    }}
+                                  >
+                                    Version Up
+                                  </Button>
                                 </div>
                               </div>
                               
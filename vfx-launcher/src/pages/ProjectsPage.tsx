@@ -7,7 +7,7 @@ import toast from 'react-hot-toast';
 // Import types
 import { Project } from '../types/project';
 import { ProjectFile } from '../types/projectFile';
-import { AppSettings } from '../types/settings';
+import { OpenedFile } from '../types/session';
 
 // Type for grouped files
 type GroupedFiles = {
@@ -86,7 +86,7 @@ const ProjectsPage: React.FC = () => {
     
     try {
       const fsRaw: Array<Omit<ProjectFile,'file_type'> & { file_type: string }> = 
-        await invoke('get_project_files', { projectId: selectedProject.id });
+        await invoke('get_project_files', { projectId: selectedProject.id, userId: user?.id });
       
       // Don't automatically scan if no files found - user must explicitly refresh
       // This prevents unnecessary scanning when opening the app
@@ -258,7 +258,7 @@ const ProjectsPage: React.FC = () => {
     setFileError(null);
     
     try {
-      const project = await invoke<Project>('get_project_details', { projectId: selectedProject.id });
+      const project = await invoke<Project>('get_project_details', { projectId: selectedProject.id, userId: user?.id });
       
       await invoke('scan_project', { 
         projectId: selectedProject.id,
@@ -281,30 +281,21 @@ const ProjectsPage: React.FC = () => {
   // Open a file using appropriate application
   const handleOpenFile = async (file: ProjectFile) => {
     try {
-      const settings = await invoke<AppSettings>('get_settings');
-      
-      let appPath = '';
-      
-      if (file.file_type === 'nk') {
-        appPath = settings.nuke_executable_path || '';
-        if (!appPath) {
-          throw new Error('Nuke executable path is not set in settings');
-        }
-      } else if (file.file_type === 'aep') {
-        appPath = settings.ae_executable_path || '';
-        if (!appPath) {
-          throw new Error('After Effects executable path is not set in settings');
-        }
-      } else {
+      if (file.file_type !== 'nk' && file.file_type !== 'aep') {
         // Don't attempt to open unsupported types
         toast.error(`Cannot open unsupported file type: ${file.file_type}`);
         return;
       }
       
-      await invoke('open_file', {
+      // The backend picks the application from the registry by file type
+      const opened = await invoke<OpenedFile>('open_file', {
         filePath: file.path,
-        appPath: appPath
+        userId: user?.id,
+        fileId: file.id
       });
+      if (opened.warning) {
+        toast(`Opened anyway: ${opened.warning}`, { icon: '⚠️' });
+      }
       
       try {
         await invoke('log_activity', {
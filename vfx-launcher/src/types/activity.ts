@@ -10,3 +10,63 @@ export interface UserActivity {
   details?: string | null;
   timestamp: string;
 }
+
+// Filters accepted by get_activity and the aggregate commands.
+// since/until take RFC 3339 or "YYYY-MM-DD"; until is exclusive.
+export interface ActivityQuery {
+  user_id?: number | null;
+  activity_types?: string[];
+  project_id?: number | null;
+  file_id?: number | null;
+  since?: string | null;
+  until?: string | null;
+  cursor?: string | null;
+  limit?: number | null;
+}
+
+export interface ActivityPage {
+  records: UserActivity[];
+  next_cursor: string | null;
+}
+
+export interface DailyCount {
+  project_id: number;
+  project_name: string | null;
+  day: string;
+  count: number;
+}
+
+export interface FileActivity {
+  file_id: number;
+  file_name: string | null;
+  project_id: number | null;
+  count: number;
+  last_activity: string;
+}
+
+export interface UserSummary {
+  user_id: number;
+  username: string | null;
+  total: number;
+  by_type: Record<string, number>;
+  first_activity: string;
+  last_activity: string;
+}
+
+export type ExportFormat = 'csv' | 'json';
+
+export interface ActivityExport {
+  path: string;
+  rows: number;
+}
+
+// A retention run that archived and removed old activity
+export interface ActivityArchive {
+  id: number;
+  machine: string;
+  user_id: number | null;
+  cutoff: string;
+  rows: number;
+  path: string;
+  created_at: string;
+}
@@ -0,0 +1,29 @@
+// A registered DCC installation (see applications.rs)
+export interface Application {
+  id: number; // 0 for a new entry
+  name: string; // e.g. 'Nuke'
+  version: string; // e.g. '15.1v3'
+  windows_path?: string | null;
+  macos_path?: string | null;
+  linux_path?: string | null;
+  default_args: string[]; // argument template, e.g. ['--nukex', '{file}']
+  file_types: string[]; // extensions without the dot, e.g. ['nk']
+}
+
+// A project, or one shot of it, locked to an application version
+export interface ApplicationPin {
+  id: number;
+  project_id: number;
+  shot_name?: string | null; // null for a project-wide pin
+  application_name: string;
+  version: string;
+  created_at: string;
+}
+
+// A named way of launching an application, e.g. 'NukeX' or 'Render-only'
+export interface LaunchVariant {
+  id: number; // 0 for a new entry
+  application_name: string;
+  name: string;
+  args: string[]; // tokens: {file}, {first}, {last}, {project}, {project_path}, {seq}, {shot}, {task}, {version}
+}
@@ -0,0 +1,11 @@
+// An effective configuration value and the layer that set it (see get_config_sources)
+export interface ConfigValue {
+  key: string; // e.g. 'database.mode'
+  value: unknown;
+  // 'default', 'system <path>', 'working directory <path>', 'user <path>', 'env VFX_DB_MODE', 'cli --set', ...
+  source: string;
+}
+
+export type DatabaseMode = 'network' | 'local';
+export type LockPolicy = 'warn' | 'block';
+export type NukeLocalizeMode = 'off' | 'copy' | 'in_place';
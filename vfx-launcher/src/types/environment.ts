@@ -0,0 +1,25 @@
+// A variable of a global, project or application environment profile (see environment.rs)
+export interface EnvironmentVariable {
+  id: number; // 0 for a new entry
+  scope: 'global' | 'project' | 'application';
+  scope_key?: string | null; // project id, or application name ('Nuke' or 'Nuke 15.1v3')
+  name: string;
+  value: string; // may reference other variables as $VAR or ${VAR}
+  operation: 'set' | 'prepend' | 'append';
+}
+
+// A variable as passed to a launched application
+export interface ResolvedVariable {
+  name: string;
+  value: string;
+  source: string; // 'context', 'global', 'project 3', 'application Nuke', ...
+}
+
+export interface Shot {
+  project_id: number;
+  name: string;
+  first_frame?: number | null;
+  last_frame?: number | null;
+  format?: string | null; // e.g. 'UHD_4K'
+  plate_path?: string | null;
+}
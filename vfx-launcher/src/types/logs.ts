@@ -0,0 +1,18 @@
+// One application log entry, from tail_logs (see diagnostics.rs)
+export interface LogEntry {
+  timestamp: string;
+  level: 'ERROR' | 'WARN' | 'INFO' | 'DEBUG' | 'TRACE';
+  module?: string | null;
+  message: string;
+  file: string;
+}
+
+// Filters for tail_logs; level is the least severe level to include
+export interface LogFilter {
+  level?: string;
+  module?: string;
+  since?: string;
+  until?: string;
+  text?: string;
+  limit?: number;
+}
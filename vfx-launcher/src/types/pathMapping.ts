@@ -0,0 +1,24 @@
+// One storage root as seen from each OS (see [[path_mappings]] in config.toml)
+export interface PathMapping {
+  name: string;
+  windows?: string | null; // e.g. 'U:'
+  macos?: string | null; // e.g. '/Volumes/Naboo'
+  linux?: string | null;
+  unc?: string | null; // e.g. '//192.168.100.9/Naboo'
+}
+
+// Result of resolve_path
+export interface PathResolution {
+  path: string;
+  mapping?: string | null; // name of the matching rule
+  canonical: string; // form stored in the database, e.g. '{naboo}/projects/foo'
+  local: string; // form used on this machine
+}
+
+// Result of localize_nuke_script / localize_project_scripts (see nuke_paths.rs)
+export interface LocalizedScript {
+  source: string;
+  output: string; // the source itself when nothing needed remapping
+  backup?: string | null; // set when rewritten in place
+  remapped: number;
+}
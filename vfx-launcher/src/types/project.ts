@@ -19,3 +19,14 @@ export interface ProjectScanConfig {
   scan_subdirs: string[]; // e.g., ["nuke", "ae", "comp"]
   last_scan_time?: string | null;
 }
+
+// Per-project membership (projects without members are visible to everyone)
+export type ProjectRole = 'owner' | 'member' | 'read_only';
+
+export interface ProjectMember {
+  project_id: number;
+  user_id: number;
+  username: string;
+  role: ProjectRole;
+  created_at: string; // ISO date string
+}
@@ -10,7 +10,20 @@ export interface ProjectFile {
   shot_name?: string | null; // Extracted shot name, if applicable
   last_modified: string; // ISO date string
   created_at: string; // ISO date string
+  missing?: boolean; // removed on disk since it was indexed
   // Add fields for locking/user later
   is_locked?: boolean;
   locked_by_user_id?: number | null;
 }
+
+// Explicit check-out of a file (see checkout_file / checkin_file)
+export interface FileLock {
+  file_id: number;
+  user_id: number;
+  username: string;
+  machine: string;
+  locked_at: string; // ISO date string
+  heartbeat_at: string; // ISO date string
+  expires_at: string; // ISO date string
+  sidecar_path?: string | null;
+}
@@ -0,0 +1,11 @@
+// Template a new shot script is created from (see scripts.rs)
+export interface ScriptTemplate {
+  id: number;
+  project_id?: number | null; // null for the studio default
+  file_type: string; // 'nk' or 'aep'
+  template_path: string;
+  // Relative to the project, e.g. 'shots/{shot}/{task}/{shot}_{task}_v{version}.{ext}'
+  script_pattern: string;
+  // Render output, available in the template as {{write_path}}
+  render_pattern?: string | null;
+}
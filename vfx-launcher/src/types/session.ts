@@ -0,0 +1,24 @@
+// A DCC launch tracked by the backend (see sessions.rs)
+export interface LaunchSession {
+  id: number;
+  user_id?: number | null;
+  username?: string | null;
+  project_id?: number | null;
+  file_id?: number | null;
+  file_path: string;
+  application: string; // e.g. 'Nuke 15.1v3'
+  machine: string;
+  pid?: number | null;
+  status: 'running' | 'exited' | 'crashed' | 'lost';
+  exit_code?: number | null;
+  started_at: string;
+  ended_at?: string | null;
+  duration_seconds?: number | null;
+  log_path?: string | null; // captured stdout/stderr, read with get_session_log
+}
+
+// Result of open_file; warning is set when the file is checked out by someone else under the "warn" lock policy
+export interface OpenedFile {
+  session_id: number | null; // null when opened through the OS file association
+  warning?: string | null;
+}
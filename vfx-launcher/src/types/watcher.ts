@@ -0,0 +1,34 @@
+// A watched project, from get_watching_projects (see watcher.rs)
+export interface WatcherStatus {
+  project_id: number;
+  is_watching: boolean;
+  path: string;
+  state: 'watching' | 'error' | 'dead';
+  error?: string | null;
+  backend: 'native' | 'poll';
+  last_event_at?: string | null;
+  last_rescan_at?: string | null;
+  // "3 added, 1 changed, 0 removed" or "failed: <error>"
+  last_rescan?: string | null;
+}
+
+// Optional backend argument of start_watching_project
+export type WatcherBackend = 'auto' | 'native' | 'poll';
+
+// Payload of the "project-files-changed" event (see events.rs)
+export interface ProjectFilesChanged {
+  project_id: number;
+  added: number[];
+  changed: number[];
+  removed: number[];
+  // Workstation that indexed the change
+  machine: string;
+  // true when another workstation made the change
+  remote: boolean;
+}
+
+// Payload of the "watcher-error" event
+export interface WatcherErrorEvent {
+  project_id: number;
+  error: string;
+}